@@ -0,0 +1,914 @@
+use crate::{
+    digest::{Challenge, cnonce, request_uri},
+    environment::substitute,
+    http::{AddedAuth, HttpRequest, HttpResponse, RequestBody, RequestType, send_request},
+    oauth::{ClientAuth, Grant, OAuth2, TokenCache},
+    sigv4::{self, Credentials, UNSIGNED_PAYLOAD, sha256_file_hex, sha256_hex},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use color_eyre::{Result, eyre::WrapErr};
+use reqwest::{
+    Client, StatusCode, Url,
+    header::{
+        AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, HeaderMap, HeaderName, HeaderValue,
+        PROXY_AUTHORIZATION,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Where an API key is sent.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+/// How a request authenticates. The credentials are added when the request
+/// is sent, so they never show up in the Headers block.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Auth {
+    #[default]
+    None,
+    Basic {
+        #[serde(default)]
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+    /// Answers the server's Digest challenge, which takes a second request.
+    Digest {
+        #[serde(default)]
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+    Bearer {
+        #[serde(default)]
+        token: String,
+    },
+    ApiKey {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        value: String,
+        #[serde(default)]
+        location: ApiKeyLocation,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2(OAuth2),
+    /// AWS Signature Version 4, for services behind IAM auth.
+    #[serde(rename = "aws-sigv4")]
+    AwsSigV4 {
+        #[serde(default)]
+        access_key: String,
+        #[serde(default)]
+        secret_key: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        session_token: String,
+        #[serde(default)]
+        region: String,
+        #[serde(default)]
+        service: String,
+    },
+}
+
+/// A field of the Auth block, as it's shown and edited.
+pub struct AuthField {
+    pub label: &'static str,
+    pub value: String,
+    /// Secrets are masked on screen.
+    pub secret: bool,
+    /// Choices are switched with a key rather than typed.
+    pub choice: bool,
+}
+
+impl AuthField {
+    fn text(label: &'static str, value: &str) -> Self {
+        Self {
+            label,
+            value: value.to_string(),
+            secret: false,
+            choice: false,
+        }
+    }
+
+    fn secret(label: &'static str, value: &str) -> Self {
+        Self {
+            secret: true,
+            ..Self::text(label, value)
+        }
+    }
+
+    fn choice(label: &'static str, value: &str) -> Self {
+        Self {
+            choice: true,
+            ..Self::text(label, value)
+        }
+    }
+}
+
+/// Replaces every character of a secret, so its length doesn't give it away
+/// either.
+pub fn mask(secret: &str) -> String {
+    if secret.is_empty() {
+        String::new()
+    } else {
+        "•".repeat(8)
+    }
+}
+
+/// A request header value as shown in the response inspector, credentials
+/// that could be replayed are masked. Digest responses and AWS signatures
+/// only work for the request they were made for, so they're shown.
+fn masked_header_value(name: &str, value: &str) -> String {
+    if name.eq_ignore_ascii_case("x-amz-security-token") {
+        return mask(value);
+    }
+    if !name.eq_ignore_ascii_case("authorization") {
+        return value.to_string();
+    }
+    match value.split_once(' ') {
+        Some((scheme, _))
+            if scheme.eq_ignore_ascii_case("digest") || scheme.starts_with("AWS4-") =>
+        {
+            value.to_string()
+        }
+        Some((scheme, credentials)) => format!("{} {}", scheme, mask(credentials)),
+        None => mask(value),
+    }
+}
+
+impl Auth {
+    pub fn is_none(&self) -> bool {
+        *self == Auth::None
+    }
+
+    /// The fields the Auth block masks.
+    fn secrets_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Auth::None => vec![],
+            Auth::Basic { password, .. } | Auth::Digest { password, .. } => vec![password],
+            Auth::Bearer { token } => vec![token],
+            Auth::ApiKey { value, .. } => vec![value],
+            Auth::OAuth2(oauth) => vec![
+                &mut oauth.client_secret,
+                &mut oauth.password,
+                &mut oauth.refresh_token,
+            ],
+            Auth::AwsSigV4 {
+                secret_key,
+                session_token,
+                ..
+            } => vec![secret_key, session_token],
+        }
+    }
+
+    /// Empties the secrets that aren't `{{variable}}` references, so they
+    /// aren't written to a collection, and tells whether there were any.
+    pub fn strip_secrets(&mut self) -> bool {
+        let mut stripped = false;
+        for secret in self.secrets_mut() {
+            let value = secret.trim();
+            let placeholder = value.starts_with("{{") && value.ends_with("}}");
+            if !value.is_empty() && !placeholder {
+                secret.clear();
+                stripped = true;
+            }
+        }
+        stripped
+    }
+
+    /// A copy with every secret masked, for showing what would be sent.
+    pub fn masked(&self) -> Auth {
+        let mut auth = self.clone();
+        for secret in auth.secrets_mut() {
+            *secret = mask(secret);
+        }
+        auth
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Auth::None => "No auth",
+            Auth::Basic { .. } => "Basic",
+            Auth::Digest { .. } => "Digest",
+            Auth::Bearer { .. } => "Bearer token",
+            Auth::ApiKey { .. } => "API key",
+            Auth::OAuth2(_) => "OAuth 2.0",
+            Auth::AwsSigV4 { .. } => "AWS Signature V4",
+        }
+    }
+
+    /// The next auth type, with its fields empty.
+    pub fn next(&self) -> Auth {
+        match self {
+            Auth::None => Auth::Basic {
+                username: String::new(),
+                password: String::new(),
+            },
+            Auth::Basic { .. } => Auth::Digest {
+                username: String::new(),
+                password: String::new(),
+            },
+            Auth::Digest { .. } => Auth::Bearer {
+                token: String::new(),
+            },
+            Auth::Bearer { .. } => Auth::ApiKey {
+                name: String::new(),
+                value: String::new(),
+                location: ApiKeyLocation::Header,
+            },
+            Auth::ApiKey { .. } => Auth::OAuth2(OAuth2::default()),
+            Auth::OAuth2(_) => Auth::AwsSigV4 {
+                access_key: String::new(),
+                secret_key: String::new(),
+                session_token: String::new(),
+                region: String::new(),
+                service: String::new(),
+            },
+            Auth::AwsSigV4 { .. } => Auth::None,
+        }
+    }
+
+    pub fn fields(&self) -> Vec<AuthField> {
+        match self {
+            Auth::None => Vec::new(),
+            Auth::Basic { username, password } | Auth::Digest { username, password } => vec![
+                AuthField::text("Username", username),
+                AuthField::secret("Password", password),
+            ],
+            Auth::Bearer { token } => vec![AuthField::secret("Token", token)],
+            Auth::ApiKey {
+                name,
+                value,
+                location,
+            } => vec![
+                AuthField::text("Key", name),
+                AuthField::secret("Value", value),
+                AuthField::choice(
+                    "Add to",
+                    match location {
+                        ApiKeyLocation::Header => "header",
+                        ApiKeyLocation::Query => "query params",
+                    },
+                ),
+            ],
+            Auth::OAuth2(oauth) => {
+                let mut fields = vec![
+                    AuthField::choice("Grant", oauth.grant.label()),
+                    AuthField::text("Token URL", &oauth.token_url),
+                    AuthField::text("Client ID", &oauth.client_id),
+                    AuthField::secret("Client secret", &oauth.client_secret),
+                    AuthField::text("Scope", &oauth.scope),
+                ];
+                match oauth.grant {
+                    Grant::ClientCredentials => {}
+                    Grant::Password => {
+                        fields.push(AuthField::text("Username", &oauth.username));
+                        fields.push(AuthField::secret("Password", &oauth.password));
+                    }
+                    Grant::RefreshToken => {
+                        fields.push(AuthField::secret("Refresh token", &oauth.refresh_token));
+                    }
+                }
+                fields.push(AuthField::choice(
+                    "Client auth",
+                    match oauth.client_auth {
+                        ClientAuth::Header => "basic header",
+                        ClientAuth::Body => "request body",
+                    },
+                ));
+                fields
+            }
+            Auth::AwsSigV4 {
+                access_key,
+                secret_key,
+                session_token,
+                region,
+                service,
+            } => vec![
+                AuthField::text("Access key", access_key),
+                AuthField::secret("Secret key", secret_key),
+                AuthField::secret("Session token", session_token),
+                AuthField::text("Region", region),
+                AuthField::text("Service", service),
+            ],
+        }
+    }
+
+    /// The text behind field `index`, `None` for choices.
+    pub fn field_mut(&mut self, index: usize) -> Option<&mut String> {
+        match (self, index) {
+            (Auth::Basic { username, .. } | Auth::Digest { username, .. }, 0) => Some(username),
+            (Auth::Basic { password, .. } | Auth::Digest { password, .. }, 1) => Some(password),
+            (Auth::Bearer { token }, 0) => Some(token),
+            (Auth::ApiKey { name, .. }, 0) => Some(name),
+            (Auth::ApiKey { value, .. }, 1) => Some(value),
+            // The text fields sit between the grant and client auth choices.
+            (auth @ Auth::OAuth2(_), index) if index > 0 => {
+                auth.texts_mut().into_iter().nth(index - 1)
+            }
+            (auth @ Auth::AwsSigV4 { .. }, index) => auth.texts_mut().into_iter().nth(index),
+            _ => None,
+        }
+    }
+
+    /// Switches the choice at field `index` to its next option.
+    pub fn toggle_field(&mut self, index: usize) {
+        let last = self.fields().len().saturating_sub(1);
+        match (self, index) {
+            (Auth::ApiKey { location, .. }, 2) => {
+                *location = match location {
+                    ApiKeyLocation::Header => ApiKeyLocation::Query,
+                    ApiKeyLocation::Query => ApiKeyLocation::Header,
+                };
+            }
+            (Auth::OAuth2(oauth), 0) => oauth.grant = oauth.grant.next(),
+            (Auth::OAuth2(oauth), index) if index == last => {
+                oauth.client_auth = match oauth.client_auth {
+                    ClientAuth::Header => ClientAuth::Body,
+                    ClientAuth::Body => ClientAuth::Header,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Every text field, for finding `{{var}}` placeholders.
+    pub fn texts(&self) -> Vec<&str> {
+        match self {
+            Auth::None => Vec::new(),
+            Auth::Basic { username, password } | Auth::Digest { username, password } => {
+                vec![username, password]
+            }
+            Auth::Bearer { token } => vec![token],
+            Auth::ApiKey { name, value, .. } => vec![name, value],
+            Auth::OAuth2(oauth) => {
+                let mut texts = vec![
+                    oauth.token_url.as_str(),
+                    &oauth.client_id,
+                    &oauth.client_secret,
+                    &oauth.scope,
+                ];
+                match oauth.grant {
+                    Grant::ClientCredentials => {}
+                    Grant::Password => texts.extend([oauth.username.as_str(), &oauth.password]),
+                    Grant::RefreshToken => texts.push(&oauth.refresh_token),
+                }
+                texts
+            }
+            Auth::AwsSigV4 {
+                access_key,
+                secret_key,
+                session_token,
+                region,
+                service,
+            } => vec![access_key, secret_key, session_token, region, service],
+        }
+    }
+
+    /// The text fields in the order they're shown. Only the ones the OAuth2
+    /// grant uses are included.
+    fn texts_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Auth::None => Vec::new(),
+            Auth::Basic { username, password } | Auth::Digest { username, password } => {
+                vec![username, password]
+            }
+            Auth::Bearer { token } => vec![token],
+            Auth::ApiKey { name, value, .. } => vec![name, value],
+            Auth::OAuth2(oauth) => {
+                let mut texts = vec![
+                    &mut oauth.token_url,
+                    &mut oauth.client_id,
+                    &mut oauth.client_secret,
+                    &mut oauth.scope,
+                ];
+                match oauth.grant {
+                    Grant::ClientCredentials => {}
+                    Grant::Password => texts.extend([&mut oauth.username, &mut oauth.password]),
+                    Grant::RefreshToken => texts.push(&mut oauth.refresh_token),
+                }
+                texts
+            }
+            Auth::AwsSigV4 {
+                access_key,
+                secret_key,
+                session_token,
+                region,
+                service,
+            } => vec![access_key, secret_key, session_token, region, service],
+        }
+    }
+
+    pub fn resolve(&self, variables: &BTreeMap<String, String>) -> Auth {
+        let mut auth = self.clone();
+        for text in auth.texts_mut() {
+            *text = substitute(text, variables);
+        }
+        auth
+    }
+
+    /// The header this auth adds, if it adds one. OAuth2 tokens and AWS
+    /// signatures are only known when the request is sent, see `authorize`.
+    pub fn header(&self) -> Option<(String, String)> {
+        match self {
+            Auth::Basic { username, password } => Some((
+                "Authorization".to_string(),
+                format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{}:{}", username, password))
+                ),
+            )),
+            Auth::Bearer { token } => Some((
+                "Authorization".to_string(),
+                format!("Bearer {}", token.trim()),
+            )),
+            Auth::ApiKey {
+                name,
+                value,
+                location: ApiKeyLocation::Header,
+            } => Some((name.trim().to_string(), value.clone())),
+            _ => None,
+        }
+    }
+
+    /// `url` with the API key added to its query, if that's where it goes.
+    pub fn apply_to_url(&self, url: &str) -> String {
+        let Auth::ApiKey {
+            name,
+            value,
+            location: ApiKeyLocation::Query,
+        } = self
+        else {
+            return url.to_string();
+        };
+        let Ok(mut url) = Url::parse(url) else {
+            return url.to_string();
+        };
+        url.query_pairs_mut().append_pair(name.trim(), value);
+        url.to_string()
+    }
+
+    /// A URL as shown on screen, with the API key's query parameter masked.
+    pub fn mask_url(&self, url: &str) -> String {
+        let Auth::ApiKey {
+            name,
+            location: ApiKeyLocation::Query,
+            ..
+        } = self
+        else {
+            return url.to_string();
+        };
+        let Some((base, rest)) = url.split_once('?') else {
+            return url.to_string();
+        };
+        let (query, fragment) = match rest.split_once('#') {
+            Some((query, fragment)) => (query, Some(fragment)),
+            None => (rest, None),
+        };
+        let query = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value))
+                    if form_urlencoded::parse(key.as_bytes())
+                        .next()
+                        .is_some_and(|(key, _)| key == name.trim()) =>
+                {
+                    format!("{}={}", key, mask(value))
+                }
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        match fragment {
+            Some(fragment) => format!("{}?{}#{}", base, query, fragment),
+            None => format!("{}?{}", base, query),
+        }
+    }
+
+    /// A request header value as shown on screen: `masked_header_value`, and
+    /// the API key's header masked too.
+    pub fn mask_header(&self, name: &str, value: &str) -> String {
+        match self {
+            Auth::ApiKey {
+                name: key,
+                location: ApiKeyLocation::Header,
+                ..
+            } if name.eq_ignore_ascii_case(key.trim()) => mask(value),
+            _ => masked_header_value(name, value),
+        }
+    }
+
+    /// Masks the credentials in what the Exchange and Info views show of a
+    /// response: the URLs and request headers of every exchange, and the
+    /// final URL.
+    pub fn mask_response(&self, response: &mut HttpResponse) {
+        for exchange in &mut response.exchanges {
+            exchange.url = self.mask_url(&exchange.url);
+            for (name, value) in &mut exchange.request_headers {
+                *value = self.mask_header(name, value);
+            }
+        }
+        response.url = self.mask_url(&response.url);
+    }
+
+    /// Adds the credentials to a request about to be sent. They replace any
+    /// header of the same name from the Headers block.
+    pub fn apply(&self, url: &mut String, headers: &mut HeaderMap) -> Result<()> {
+        if let Some((name, value)) = self.header() {
+            headers.insert(HeaderName::from_str(&name)?, HeaderValue::from_str(&value)?);
+        }
+        *url = self.apply_to_url(url);
+
+        Ok(())
+    }
+
+    /// Adds what can only be worked out right before sending: the OAuth2
+    /// token, fetched or from the cache, or the AWS signature over the final
+    /// URL, headers and body. Runs after `apply`.
+    pub async fn authorize(
+        &self,
+        client: &Client,
+        tokens: &TokenCache,
+        request: &mut HttpRequest,
+    ) -> Result<()> {
+        match self {
+            Auth::OAuth2(oauth) => tokens
+                .authorize(client, oauth, &mut request.headers)
+                .await
+                .wrap_err("Could not get an OAuth 2.0 token"),
+            Auth::AwsSigV4 {
+                access_key,
+                secret_key,
+                session_token,
+                region,
+                service,
+            } => {
+                let payload_hash = match &request.body {
+                    None => sha256_hex(b""),
+                    Some(RequestBody::Text(text)) => sha256_hex(text.as_bytes()),
+                    Some(RequestBody::File(path)) => sha256_file_hex(path).await?,
+                    Some(RequestBody::Multipart(_)) => UNSIGNED_PAYLOAD.to_string(),
+                };
+                let credentials = Credentials {
+                    access_key: access_key.trim(),
+                    secret_key: secret_key.trim(),
+                    session_token: session_token.trim(),
+                    region: region.trim(),
+                    service: service.trim(),
+                };
+                sigv4::sign(
+                    &credentials,
+                    request.request_type.method().as_str(),
+                    &request.url,
+                    &mut request.headers,
+                    &payload_hash,
+                )
+                .wrap_err("Could not sign the request")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// What `apply` and `authorize` add to a request.
+    fn added(&self) -> AddedAuth {
+        let headers = match self {
+            Auth::None => vec![],
+            Auth::ApiKey {
+                name,
+                location: ApiKeyLocation::Header,
+                ..
+            } => HeaderName::from_str(name.trim()).into_iter().collect(),
+            Auth::ApiKey { .. } => vec![],
+            Auth::AwsSigV4 { .. } => vec![
+                AUTHORIZATION,
+                HeaderName::from_static("x-amz-date"),
+                HeaderName::from_static("x-amz-content-sha256"),
+                HeaderName::from_static("x-amz-security-token"),
+            ],
+            _ => vec![AUTHORIZATION],
+        };
+        let query = match self {
+            Auth::ApiKey {
+                name,
+                location: ApiKeyLocation::Query,
+                ..
+            } => Some(name.trim().to_string()),
+            _ => None,
+        };
+        AddedAuth { headers, query }
+    }
+
+    /// Authorizes and sends a request. A Digest challenge is answered with a
+    /// second request to the URL that made it, the response keeps every
+    /// exchange.
+    pub async fn send(
+        &self,
+        client: Client,
+        tokens: &TokenCache,
+        mut request: HttpRequest,
+    ) -> Result<HttpResponse, String> {
+        self.authorize(&client, tokens, &mut request)
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+        request.added_auth = self.added();
+        let Auth::Digest { username, password } = self else {
+            let response = send_request(client, request).await?;
+            // The token was revoked or expired early, the next send gets a
+            // new one.
+            if response.status == StatusCode::UNAUTHORIZED
+                && let Auth::OAuth2(oauth) = self
+            {
+                tokens.invalidate(oauth);
+            }
+            return Ok(response);
+        };
+
+        let mut retry = request.clone();
+        let challenged = send_request(client.clone(), request).await?;
+        let challenge = Challenge::pick(
+            challenged
+                .headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("www-authenticate"))
+                .map(|(_, value)| value.as_str()),
+        );
+        let (StatusCode::UNAUTHORIZED, Some(challenge)) = (challenged.status, challenge) else {
+            return Ok(challenged);
+        };
+
+        // The challenge is answered where it was made. After redirects that's
+        // the last hop, which is retried directly rather than by following
+        // the redirects again.
+        if challenged.url != retry.url {
+            if let Some(last) = challenged.exchanges.last()
+                && last.method != retry.request_type.to_string()
+            {
+                // Only a switch to GET happens on the way, the body is dropped.
+                retry.request_type = RequestType::GET;
+                retry.body = None;
+                retry.headers.remove(CONTENT_TYPE);
+                retry.headers.remove(CONTENT_LENGTH);
+            }
+            let from = Url::parse(&retry.url).map_err(|e| e.to_string())?;
+            let to = Url::parse(&challenged.url).map_err(|e| e.to_string())?;
+            if to.host_str() != from.host_str()
+                || to.port_or_known_default() != from.port_or_known_default()
+            {
+                retry.headers.remove(PROXY_AUTHORIZATION);
+                retry.headers.remove(COOKIE);
+            }
+            retry.url = challenged.url.clone();
+        }
+        let uri = request_uri(&retry.url).map_err(|e| e.to_string())?;
+        let authorization = challenge.response(
+            username,
+            password,
+            retry.request_type.method().as_str(),
+            &uri,
+            &cnonce(),
+        );
+        retry.headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&authorization).map_err(|e| e.to_string())?,
+        );
+        let mut response = send_request(client, retry).await?;
+        let mut exchanges = challenged.exchanges;
+        exchanges.append(&mut response.exchanges);
+        response.exchanges = exchanges;
+
+        Ok(response)
+    }
+
+    /// A one line description for previews, with the secrets masked.
+    pub fn summary(&self) -> Option<String> {
+        match self {
+            Auth::None => None,
+            Auth::Basic { username, password } => {
+                Some(format!("Basic auth as {} ({})", username, mask(password)))
+            }
+            Auth::Digest { username, password } => {
+                Some(format!("Digest auth as {} ({})", username, mask(password)))
+            }
+            Auth::Bearer { token } => Some(format!("Bearer token {}", mask(token))),
+            Auth::ApiKey {
+                name,
+                value,
+                location,
+            } => Some(format!(
+                "API key {} = {} in the {}",
+                name,
+                mask(value),
+                match location {
+                    ApiKeyLocation::Header => "headers",
+                    ApiKeyLocation::Query => "query",
+                }
+            )),
+            Auth::OAuth2(oauth) => Some(format!(
+                "OAuth 2.0 {} grant from {}",
+                oauth.grant.label(),
+                oauth.token_url
+            )),
+            Auth::AwsSigV4 {
+                access_key,
+                region,
+                service,
+                ..
+            } => Some(format!(
+                "AWS Signature V4 for {} in {} as {}",
+                service, region, access_key
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http, tls::TlsConfig};
+    use std::sync::{Arc, Mutex};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn only_placeholder_secrets_are_kept() {
+        let mut auth = Auth::AwsSigV4 {
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "{{aws_secret}}".to_string(),
+            session_token: "token".to_string(),
+            region: "us-east-1".to_string(),
+            service: "s3".to_string(),
+        };
+        assert!(auth.strip_secrets());
+        assert!(
+            auth == Auth::AwsSigV4 {
+                access_key: "AKIDEXAMPLE".to_string(),
+                secret_key: "{{aws_secret}}".to_string(),
+                session_token: String::new(),
+                region: "us-east-1".to_string(),
+                service: "s3".to_string(),
+            }
+        );
+        assert!(!auth.strip_secrets());
+
+        let mut auth = Auth::Bearer {
+            token: " {{token}} ".to_string(),
+        };
+        assert!(!auth.strip_secrets());
+    }
+
+    /// Answers one connection per response, in order, and keeps the requests
+    /// it was sent.
+    async fn serve(responses: &[&str]) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        let responses: Vec<String> = responses.iter().map(|r| r.to_string()).collect();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0; 4096];
+                let read = socket.read(&mut buffer).await.unwrap();
+                received
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buffer[..read]).to_string());
+                let response =
+                    format!("{}content-length: 0\r\nconnection: close\r\n\r\n", response);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (base, requests)
+    }
+
+    #[tokio::test]
+    async fn api_key_is_not_sent_on_to_another_host() {
+        let keys = [
+            Auth::ApiKey {
+                name: "X-API-Key".to_string(),
+                value: "hunter2".to_string(),
+                location: ApiKeyLocation::Header,
+            },
+            Auth::ApiKey {
+                name: "api_key".to_string(),
+                value: "hunter2".to_string(),
+                location: ApiKeyLocation::Query,
+            },
+        ];
+        for auth in keys {
+            // Another port is another host as far as credentials go.
+            let (other, landed) = serve(&["HTTP/1.1 200 OK\r\n"]).await;
+            // A server may echo the key back in the Location.
+            let query = match &auth {
+                Auth::ApiKey {
+                    location: ApiKeyLocation::Query,
+                    ..
+                } => "api_key=hunter2&page=2",
+                _ => "page=2",
+            };
+            let redirect = format!(
+                "HTTP/1.1 302 Found\r\nlocation: {}/landing?{}\r\n",
+                other, query
+            );
+            let (base, requests) = serve(&[&redirect]).await;
+
+            let mut request = http::get(format!("{}/start", base));
+            auth.apply(&mut request.url, &mut request.headers).unwrap();
+            let response = auth
+                .send(
+                    http::local_client(&TlsConfig::default()),
+                    &TokenCache::default(),
+                    request,
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status, StatusCode::OK);
+
+            assert!(requests.lock().unwrap()[0].contains("hunter2"));
+            let landed = &landed.lock().unwrap()[0];
+            assert!(landed.starts_with("GET /landing?page=2 "), "{}", landed);
+            assert!(!landed.contains("hunter2"), "{}", landed);
+        }
+    }
+
+    #[tokio::test]
+    async fn digest_challenge_after_a_redirect_is_answered_at_the_last_hop() {
+        let (base, requests) = serve(&[
+            "HTTP/1.1 302 Found\r\nlocation: /final\r\n",
+            "HTTP/1.1 401 Unauthorized\r\nwww-authenticate: Digest realm=\"test\", nonce=\"abc123\", qop=\"auth\"\r\n",
+            "HTTP/1.1 200 OK\r\n",
+        ])
+        .await;
+
+        let client = http::local_client(&TlsConfig::default());
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        let request = HttpRequest {
+            request_type: RequestType::POST,
+            url: format!("{}/start", base),
+            headers,
+            body: Some(RequestBody::Text("hello".to_string())),
+            cookies: None,
+            redirects: Default::default(),
+            added_auth: Default::default(),
+        };
+        let auth = Auth::Digest {
+            username: "user".to_string(),
+            password: "password".to_string(),
+        };
+
+        let response = auth
+            .send(client, &TokenCache::default(), request)
+            .await
+            .unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.exchanges.len(), 3);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("POST /start "));
+        assert!(requests[1].starts_with("GET /final "));
+        // Straight to the final hop, with the method the redirect switched to.
+        assert!(requests[2].starts_with("GET /final "));
+        assert!(requests[2].contains("uri=\"/final\""));
+        assert!(!requests[2].contains("hello"));
+    }
+
+    #[test]
+    fn api_key_is_masked_where_it_is_shown() {
+        let header = Auth::ApiKey {
+            name: "X-API-Key".to_string(),
+            value: "hunter2".to_string(),
+            location: ApiKeyLocation::Header,
+        };
+        assert_eq!(header.mask_header("x-api-key", "hunter2"), "••••••••");
+        assert_eq!(header.mask_header("Accept", "*/*"), "*/*");
+        assert_eq!(
+            header.mask_header("Authorization", "Bearer hunter2"),
+            "Bearer ••••••••"
+        );
+
+        let query = Auth::ApiKey {
+            name: "api key".to_string(),
+            value: "hunter2".to_string(),
+            location: ApiKeyLocation::Query,
+        };
+        let url = query.apply_to_url("https://example.com/?page=1");
+        assert_eq!(
+            query.mask_url(&url),
+            "https://example.com/?page=1&api+key=••••••••"
+        );
+        assert_eq!(
+            query.mask_url("https://example.com/?api%20key=hunter2#top"),
+            "https://example.com/?api%20key=••••••••#top"
+        );
+        assert_eq!(header.mask_url(&url), url);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    DisplayString,
+    auth::{Auth, mask},
+};
+use crossterm::event::KeyCode;
+use ratatui::{prelude::*, widgets::*};
+
+/// The Auth block of the Request/Reply tab. The first row is the auth type,
+/// the rows after it are the fields of that type.
+pub struct AuthEditor {
+    pub auth: Auth,
+    selected: usize,
+    input: DisplayString,
+    /// Whether secrets are shown as typed rather than masked.
+    reveal: bool,
+}
+
+impl AuthEditor {
+    pub fn new() -> Self {
+        Self {
+            auth: Auth::None,
+            selected: 0,
+            input: DisplayString::new(String::new()),
+            reveal: false,
+        }
+    }
+
+    pub fn set_auth(&mut self, auth: Auth) {
+        self.auth = auth;
+        self.selected = 0;
+        self.input.edit_mode = false;
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.input.edit_mode
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        self.input.add_str(text.lines().next().unwrap_or_default());
+    }
+
+    fn row_count(&self) -> usize {
+        1 + self.auth.fields().len()
+    }
+
+    /// Activates the selected row: the type row and choices switch to their
+    /// next option, text fields start editing.
+    fn activate(&mut self) {
+        if self.selected == 0 {
+            self.auth = self.auth.next();
+            return;
+        }
+        let index = self.selected - 1;
+        if self
+            .auth
+            .fields()
+            .get(index)
+            .is_some_and(|field| field.choice)
+        {
+            self.auth.toggle_field(index);
+        } else if let Some(value) = self.auth.field_mut(index) {
+            self.input.update_value(value.clone());
+            self.input.edit_mode = true;
+        }
+    }
+
+    /// Handles keys while the Auth block is active, returning whether the key
+    /// was consumed. Moving past the first or last row isn't, so the arrows
+    /// still move between blocks.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.is_editing() {
+            match code {
+                KeyCode::Char(c) => self.input.add_char(c),
+                KeyCode::Backspace => self.input.remove_last_char(),
+                KeyCode::Enter => {
+                    if let Some(value) = self.auth.field_mut(self.selected.saturating_sub(1)) {
+                        *value = self.input.value.clone();
+                    }
+                    self.input.edit_mode = false;
+                }
+                KeyCode::Esc => self.input.edit_mode = false,
+                _ => {}
+            }
+            return true;
+        }
+
+        match code {
+            KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.row_count() => {
+                self.selected += 1;
+            }
+            KeyCode::Up | KeyCode::Char('k') if self.selected > 0 => self.selected -= 1,
+            KeyCode::Char('e') | KeyCode::Char(' ') | KeyCode::Enter => self.activate(),
+            KeyCode::Char('t') => {
+                self.auth = self.auth.next();
+                self.selected = 0;
+            }
+            KeyCode::Char('*') => self.reveal = !self.reveal,
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, active: bool) {
+        let mut title = format!("Auth - {}", self.auth.label());
+        if self.is_editing() {
+            title.push_str(" - Editing");
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_style(
+                Style::default()
+                    .fg(Color::LightYellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .border_style(if active {
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::LightBlue)
+            });
+
+        let label_style = Style::default().fg(Color::Gray);
+        let selected_style = if active {
+            Style::default().bg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Type: ", label_style),
+                Span::raw(self.auth.label()),
+            ])
+            .style(if self.selected == 0 {
+                selected_style
+            } else {
+                Style::default()
+            }),
+        ];
+        for (index, field) in self.auth.fields().into_iter().enumerate() {
+            let selected = self.selected == index + 1;
+            let value = if selected && self.is_editing() {
+                let typed = if field.secret && !self.reveal {
+                    "•".repeat(self.input.value.chars().count())
+                } else {
+                    self.input.value.clone()
+                };
+                format!("{}█", typed)
+            } else if field.secret && !self.reveal {
+                mask(&field.value)
+            } else {
+                field.value
+            };
+            lines.push(
+                Line::from(vec![
+                    Span::styled(format!("{}: ", field.label), label_style),
+                    Span::raw(value),
+                ])
+                .style(if selected {
+                    selected_style
+                } else {
+                    Style::default()
+                }),
+            );
+        }
+        if self.auth.is_none() {
+            lines.push(Line::styled(
+                "Press [t] to pick an auth type",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+
+        // Keeps the selected row in view when the fields don't all fit.
+        let height = area.height.saturating_sub(2) as usize;
+        let scroll = (self.selected + 1).saturating_sub(height) as u16;
+        frame.render_widget(Paragraph::new(lines).block(block).scroll((scroll, 0)), area);
+    }
+}
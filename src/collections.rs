@@ -0,0 +1,305 @@
+use crate::{http::SavedRequest, http_file, storage::workspace_dir};
+use color_eyre::{Result, eyre::eyre};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+static REQUEST_EXTENSION: &str = "json";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    Folder,
+    Request,
+    /// A `.http` file, which expands into the requests inside it.
+    HttpFile,
+    /// Request number `n` of the `.http` file at the node's path.
+    HttpRequest(usize),
+}
+
+/// One row of the collections tree, flattened so it can be shown in a list.
+pub struct CollectionNode {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: NodeKind,
+    pub depth: usize,
+}
+
+/// Saved requests live under `.pingpong/collections` in the workspace, one
+/// pretty-printed JSON file per request. Top level directories are the
+/// collections, anything below them is a folder.
+pub fn collections_dir() -> PathBuf {
+    workspace_dir().join("collections")
+}
+
+/// Walks the collections directory, only descending into expanded folders.
+/// Folders come before requests and both are sorted by name. `.http` files
+/// opened from elsewhere are listed first.
+pub fn load_tree(expanded: &HashSet<PathBuf>, opened_files: &[PathBuf]) -> Vec<CollectionNode> {
+    let mut nodes = Vec::new();
+    for path in opened_files {
+        push_http_file(path.clone(), 0, expanded, &mut nodes);
+    }
+    walk(&collections_dir(), 0, expanded, &mut nodes);
+    nodes
+}
+
+fn push_http_file(
+    path: PathBuf,
+    depth: usize,
+    expanded: &HashSet<PathBuf>,
+    nodes: &mut Vec<CollectionNode>,
+) {
+    nodes.push(CollectionNode {
+        name: file_name(&path),
+        path: path.clone(),
+        kind: NodeKind::HttpFile,
+        depth,
+    });
+    if !expanded.contains(&path) {
+        return;
+    }
+
+    let Ok(file) = http_file::load(&path) else {
+        return;
+    };
+    for (index, request) in file.requests.into_iter().enumerate() {
+        nodes.push(CollectionNode {
+            name: request.name,
+            path: path.clone(),
+            kind: NodeKind::HttpRequest(index),
+            depth: depth + 1,
+        });
+    }
+}
+
+fn walk(dir: &Path, depth: usize, expanded: &HashSet<PathBuf>, nodes: &mut Vec<CollectionNode>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut folders = Vec::new();
+    let mut requests = Vec::new();
+    let mut http_files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            folders.push(path);
+        } else if path.extension().is_some_and(|ext| ext == REQUEST_EXTENSION) {
+            requests.push(path);
+        } else if http_file::is_http_file(&path) {
+            http_files.push(path);
+        }
+    }
+    folders.sort();
+    requests.sort();
+    http_files.sort();
+
+    for path in folders {
+        nodes.push(CollectionNode {
+            name: file_name(&path),
+            path: path.clone(),
+            kind: NodeKind::Folder,
+            depth,
+        });
+        if expanded.contains(&path) {
+            walk(&path, depth + 1, expanded, nodes);
+        }
+    }
+
+    for path in http_files {
+        push_http_file(path, depth, expanded, nodes);
+    }
+
+    for path in requests {
+        nodes.push(CollectionNode {
+            name: request_name(&path),
+            path,
+            kind: NodeKind::Request,
+            depth,
+        });
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+pub fn request_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Turns a user supplied name into something safe to use as a file name.
+pub fn sanitize(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Resolves a `collection/folder/request name` path typed by the user into the
+/// file the request is stored in.
+pub fn request_path(location: &str) -> Result<PathBuf> {
+    let parts: Vec<String> = location
+        .split('/')
+        .map(sanitize)
+        .filter(|part| !part.is_empty() && part != "." && part != "..")
+        .collect();
+    if parts.len() < 2 {
+        return Err(eyre!("Use collection/name or collection/folder/name"));
+    }
+
+    let mut path = collections_dir();
+    for part in &parts[..parts.len() - 1] {
+        path.push(part);
+    }
+    path.push(format!("{}.{}", parts[parts.len() - 1], REQUEST_EXTENSION));
+
+    Ok(path)
+}
+
+/// The `collection/folder/name` form of a request file, used to prefill prompts.
+pub fn request_location(path: &Path) -> String {
+    let relative = path
+        .strip_prefix(collections_dir())
+        .unwrap_or(path)
+        .with_extension("");
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A file for a new request called `name` in `dir`, numbered "<name> 2",
+/// "<name> 3", ... if the name is already taken. Used by the importers.
+pub fn unique_request_path(dir: &Path, name: &str) -> PathBuf {
+    unique_path(dir, name, Some(REQUEST_EXTENSION))
+}
+
+/// Like `unique_request_path`, for folders.
+pub fn unique_folder_path(dir: &Path, name: &str) -> PathBuf {
+    unique_path(dir, name, None)
+}
+
+fn unique_path(dir: &Path, name: &str, extension: Option<&str>) -> PathBuf {
+    let name = match sanitize(name) {
+        name if name.is_empty() || name == "." || name == ".." => "untitled".to_string(),
+        name => name,
+    };
+    let mut number = 1;
+    loop {
+        let mut file_name = if number == 1 {
+            name.clone()
+        } else {
+            format!("{} {}", name, number)
+        };
+        if let Some(extension) = extension {
+            file_name = format!("{}.{}", file_name, extension);
+        }
+        let candidate = dir.join(file_name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        number += 1;
+    }
+}
+
+pub fn load_request(path: &Path) -> Result<SavedRequest> {
+    let contents = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// Collections are meant to be shared, so auth secrets and credential
+/// headers are only saved as `{{variable}}` references. Returns what was left
+/// out.
+pub fn save_request(path: &Path, request: &SavedRequest) -> Result<Vec<String>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut request = request.clone();
+    let stripped = request.strip_secrets();
+    let mut contents = serde_json::to_string_pretty(&request)?;
+    contents.push('\n');
+    fs::write(path, contents)?;
+
+    Ok(stripped)
+}
+
+pub fn create_folder(parent: &Path, name: &str) -> Result<PathBuf> {
+    let name = sanitize(name);
+    if name.is_empty() {
+        return Err(eyre!("Folder name can't be empty"));
+    }
+    let path = parent.join(name);
+    fs::create_dir_all(&path)?;
+
+    Ok(path)
+}
+
+/// Renames a folder or request in place, keeping it in the same parent folder.
+pub fn rename(path: &Path, kind: NodeKind, new_name: &str) -> Result<PathBuf> {
+    let new_name = sanitize(new_name);
+    if new_name.is_empty() {
+        return Err(eyre!("Name can't be empty"));
+    }
+    let file_name = match kind {
+        NodeKind::Folder => new_name,
+        NodeKind::Request => format!("{}.{}", new_name, REQUEST_EXTENSION),
+        NodeKind::HttpFile => match path.extension() {
+            Some(ext) => format!("{}.{}", new_name, ext.to_string_lossy()),
+            None => new_name,
+        },
+        NodeKind::HttpRequest(_) => {
+            return Err(eyre!("Rename requests in a .http file with @name"));
+        }
+    };
+    let new_path = path.with_file_name(file_name);
+    if new_path.exists() {
+        return Err(eyre!("{} already exists", new_path.display()));
+    }
+    fs::rename(path, &new_path)?;
+
+    Ok(new_path)
+}
+
+/// Copies a request next to the original as "<name> copy", "<name> copy 2", ...
+pub fn duplicate(path: &Path) -> Result<PathBuf> {
+    let name = request_name(path);
+    let mut copy_number = 1;
+    let new_path = loop {
+        let suffix = if copy_number == 1 {
+            " copy".to_string()
+        } else {
+            format!(" copy {}", copy_number)
+        };
+        let candidate = path.with_file_name(format!("{}{}.{}", name, suffix, REQUEST_EXTENSION));
+        if !candidate.exists() {
+            break candidate;
+        }
+        copy_number += 1;
+    };
+    fs::copy(path, &new_path)?;
+
+    Ok(new_path)
+}
+
+pub fn delete(path: &Path, kind: NodeKind) -> Result<()> {
+    match kind {
+        NodeKind::Folder => fs::remove_dir_all(path)?,
+        NodeKind::Request | NodeKind::HttpFile => fs::remove_file(path)?,
+        NodeKind::HttpRequest(_) => {
+            return Err(eyre!("Delete requests by editing the .http file"));
+        }
+    }
+
+    Ok(())
+}
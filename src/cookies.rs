@@ -0,0 +1,402 @@
+use crate::storage::data_dir;
+use chrono::{DateTime, TimeDelta, Utc};
+use color_eyre::{Result, eyre::eyre};
+use cookie::Cookie;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// A cookie set by a server, or added by hand in the Cookies tab.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// Set without a `Domain` attribute, so it's only sent back to that exact
+    /// host and not to its subdomains.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub host_only: bool,
+    pub path: String,
+    /// `None` for a session cookie. Those are kept until they're deleted,
+    /// there's no browser session to end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secure: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub http_only: bool,
+}
+
+impl StoredCookie {
+    fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= Utc::now())
+    }
+
+    fn same_key(&self, other: &StoredCookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str().map(|host| host.to_lowercase()) else {
+            return false;
+        };
+        let domain_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain)
+        };
+
+        domain_matches
+            && path_match(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+            && !self.is_expired()
+    }
+
+    /// The cookie in `Set-Cookie` form, as the edit prompt shows it.
+    pub fn to_set_cookie(&self) -> String {
+        let mut text = format!(
+            "{}={}; Domain={}; Path={}",
+            self.name, self.value, self.domain, self.path
+        );
+        if let Some(expires) = self.expires {
+            text.push_str(&format!(
+                "; Expires={}",
+                expires.format("%a, %d %b %Y %H:%M:%S GMT")
+            ));
+        }
+        if self.secure {
+            text.push_str("; Secure");
+        }
+        if self.http_only {
+            text.push_str("; HttpOnly");
+        }
+        text
+    }
+
+    /// When it expires and its flags, for the Cookies tab.
+    pub fn details(&self) -> String {
+        let mut details = vec![match self.expires {
+            Some(expires) => format!(
+                "expires {}",
+                expires
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+            ),
+            None => "session".to_string(),
+        }];
+        if self.host_only {
+            details.push("host only".to_string());
+        }
+        if self.secure {
+            details.push("Secure".to_string());
+        }
+        if self.http_only {
+            details.push("HttpOnly".to_string());
+        }
+        details.join(", ")
+    }
+}
+
+/// `host` is `domain` or one of its subdomains. IP addresses only match
+/// themselves.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<IpAddr>().is_err())
+}
+
+/// The request path is the cookie path or below it.
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The path a cookie gets when `Set-Cookie` doesn't give one, the request's
+/// directory.
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(i) => url.path()[..i].to_string(),
+    }
+}
+
+/// Parses a `Set-Cookie` value. `None` when a cookie for that host would be
+/// refused, e.g. when the domain is another site's.
+fn parse_set_cookie(url: &Url, header: &str) -> Option<StoredCookie> {
+    let cookie = Cookie::parse(header).ok()?;
+    let host = url.host_str()?.to_lowercase();
+    let (domain, host_only) = match cookie.domain() {
+        Some(domain) if !domain.is_empty() => {
+            let domain = domain.to_lowercase();
+            // A bare top-level domain would reach every site under it.
+            if !domain_match(&host, &domain) || (!domain.contains('.') && domain != host) {
+                return None;
+            }
+            (domain, false)
+        }
+        _ => (host, true),
+    };
+    let path = match cookie.path() {
+        Some(path) if path.starts_with('/') => path.to_string(),
+        _ => default_path(url),
+    };
+    // Max-Age wins over Expires when both are given.
+    let expires = match (cookie.max_age(), cookie.expires_datetime()) {
+        // A server can send any number, out of range ones are clamped.
+        (Some(max_age), _) => Some(
+            TimeDelta::try_seconds(max_age.whole_seconds())
+                .and_then(|max_age| Utc::now().checked_add_signed(max_age))
+                .unwrap_or(if max_age.is_negative() {
+                    DateTime::<Utc>::MIN_UTC
+                } else {
+                    DateTime::<Utc>::MAX_UTC
+                }),
+        ),
+        (None, Some(expires)) => DateTime::from_timestamp(expires.unix_timestamp(), 0),
+        (None, None) => None,
+    };
+
+    Some(StoredCookie {
+        name: cookie.name().to_string(),
+        value: cookie.value().to_string(),
+        domain,
+        host_only,
+        path,
+        expires,
+        secure: cookie.secure().unwrap_or(false),
+        http_only: cookie.http_only().unwrap_or(false),
+    })
+}
+
+/// Parses the edit prompt's `Set-Cookie` form. Unlike a response, there's no
+/// request to take the domain from, so it has to be given.
+pub fn parse_cookie(input: &str, previous: Option<&StoredCookie>) -> Result<StoredCookie> {
+    let cookie = Cookie::parse(input.trim()).map_err(|e| eyre!("Invalid cookie: {}", e))?;
+    let domain = cookie
+        .domain()
+        .map(|domain| domain.trim().to_lowercase())
+        .filter(|domain| !domain.is_empty())
+        .ok_or_else(|| eyre!("Expected name=value; Domain=example.com"))?;
+    let url = Url::parse(&format!("http://{}/", domain))
+        .map_err(|_| eyre!("Invalid domain: {}", domain))?;
+    let mut stored =
+        parse_set_cookie(&url, input.trim()).ok_or_else(|| eyre!("Invalid domain: {}", domain))?;
+    // Editing a host-only cookie shows its host as the domain, it stays
+    // host-only unless the domain is changed.
+    stored.host_only =
+        previous.is_some_and(|previous| previous.host_only && previous.domain == stored.domain);
+
+    Ok(stored)
+}
+
+/// What's written to disk for each environment.
+#[derive(Serialize, Deserialize, Default)]
+struct Jar {
+    /// Cookies are opt-in, nothing is sent or stored until the jar is
+    /// switched on.
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    cookies: Vec<StoredCookie>,
+}
+
+/// The cookies of the active environment, shared by every request sent from
+/// the Request/Reply tab. Kept in the data directory, `cookies.json` without
+/// an environment and `cookies/<environment>.json` with one.
+#[derive(Clone, Default)]
+pub struct CookieJar {
+    /// `None` for a jar whose file couldn't be read, it's never saved so the
+    /// file is left as it is.
+    path: Option<PathBuf>,
+    jar: Arc<Mutex<Jar>>,
+}
+
+impl CookieJar {
+    /// The jar, even if a thread panicked while holding it, so one bad
+    /// response doesn't take every later lock down with it.
+    fn lock(&self) -> MutexGuard<'_, Jar> {
+        self.jar.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Loads the jar of an environment, an empty one if there's none yet.
+    pub fn load(environment: Option<&str>) -> Result<CookieJar> {
+        let path = match environment {
+            Some(name) => data_dir().join("cookies").join(format!("{}.json", name)),
+            None => data_dir().join("cookies.json"),
+        };
+        let mut jar: Jar = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?,
+            Err(_) => Jar::default(),
+        };
+        jar.cookies.retain(|cookie| !cookie.is_expired());
+
+        Ok(CookieJar {
+            path: Some(path),
+            jar: Arc::new(Mutex::new(jar)),
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents = serde_json::to_string_pretty(&*self.lock())?;
+        contents.push('\n');
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.lock().enabled
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.lock().enabled = enabled;
+    }
+
+    /// Cookies that haven't expired, by domain, then path and name.
+    pub fn cookies(&self) -> Vec<StoredCookie> {
+        let mut cookies: Vec<StoredCookie> = self
+            .lock()
+            .cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired())
+            .cloned()
+            .collect();
+        cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+        cookies
+    }
+
+    /// The `Cookie` header for a request, longer paths first. `None` when
+    /// the jar is off or no cookie matches.
+    pub fn header(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        let jar = self.lock();
+        if !jar.enabled {
+            return None;
+        }
+
+        let mut cookies: Vec<&StoredCookie> = jar
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(&url))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        Some(
+            cookies
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+
+    /// Stores the `Set-Cookie` headers of a response to `url`. A cookie that
+    /// has already expired deletes the one it replaces.
+    pub fn store<'a>(&self, url: &str, headers: impl Iterator<Item = &'a str>) {
+        let Ok(url) = Url::parse(url) else {
+            return;
+        };
+        let mut jar = self.lock();
+        if !jar.enabled {
+            return;
+        }
+
+        for cookie in headers.filter_map(|header| parse_set_cookie(&url, header)) {
+            jar.cookies.retain(|stored| !stored.same_key(&cookie));
+            if !cookie.is_expired() {
+                jar.cookies.push(cookie);
+            }
+        }
+    }
+
+    /// Adds a cookie, replacing `previous` when one is being edited.
+    pub fn set(&self, cookie: StoredCookie, previous: Option<&StoredCookie>) {
+        let mut jar = self.lock();
+        jar.cookies.retain(|stored| {
+            !stored.same_key(&cookie) && previous.is_none_or(|previous| !stored.same_key(previous))
+        });
+        jar.cookies.push(cookie);
+    }
+
+    pub fn remove(&self, cookie: &StoredCookie) {
+        self.lock()
+            .cookies
+            .retain(|stored| !stored.same_key(cookie));
+    }
+
+    /// Deletes every cookie of a domain, whatever its path.
+    pub fn remove_domain(&self, domain: &str) {
+        self.lock().cookies.retain(|stored| stored.domain != domain);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jar() -> CookieJar {
+        let jar = CookieJar::default();
+        jar.set_enabled(true);
+        jar
+    }
+
+    #[test]
+    fn huge_max_age_is_clamped() {
+        let jar = jar();
+        jar.store(
+            "http://example.com/",
+            [
+                "overflows=1; Max-Age=100000000000000",
+                "out-of-bounds=2; Max-Age=99999999999999999999",
+            ]
+            .into_iter(),
+        );
+
+        let cookies = jar.cookies();
+        assert_eq!(cookies.len(), 2);
+        for cookie in &cookies {
+            assert_eq!(cookie.expires, Some(DateTime::<Utc>::MAX_UTC));
+            // The jar can still be saved and read back.
+            let saved = serde_json::to_string(cookie).unwrap();
+            assert!(serde_json::from_str::<StoredCookie>(&saved).unwrap() == *cookie);
+        }
+        assert_eq!(
+            jar.header("http://example.com/").as_deref(),
+            Some("overflows=1; out-of-bounds=2")
+        );
+    }
+
+    #[test]
+    fn negative_max_age_deletes_the_cookie() {
+        let jar = jar();
+        jar.store("http://example.com/", ["session=1"].into_iter());
+        assert_eq!(jar.cookies().len(), 1);
+
+        jar.store(
+            "http://example.com/",
+            [
+                "session=1; Max-Age=-1",
+                "other=2; Max-Age=-99999999999999999999",
+            ]
+            .into_iter(),
+        );
+        assert!(jar.cookies().is_empty());
+        assert_eq!(jar.header("http://example.com/"), None);
+    }
+}
@@ -0,0 +1,598 @@
+use crate::{
+    auth::{ApiKeyLocation, Auth},
+    http::{
+        BodyMode, KeyValue, Redirects, RequestType, SavedRequest, enabled_fields, header_value,
+    },
+};
+use color_eyre::{Result, eyre::eyre};
+
+/// Splits a command line into words the way a POSIX shell would, including
+/// `$'...'` strings as produced by browser devtools.
+fn shell_words(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(eyre!("Unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(eyre!("Unterminated double quote")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(eyre!("Unterminated double quote")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => word.push('\n'),
+                            Some('t') => word.push('\t'),
+                            Some('r') => word.push('\r'),
+                            Some(c) => word.push(c),
+                            None => return Err(eyre!("Unterminated $' quote")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(eyre!("Unterminated $' quote")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') | Some('\r') => {}
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+                None => {}
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Replaces any header called `name`, for flags like `-A` that set a single
+/// header.
+fn set_header(headers: &mut Vec<KeyValue>, name: &str, value: String) {
+    headers.retain(|header| !header.name.eq_ignore_ascii_case(name));
+    headers.push(KeyValue::new(name, value));
+}
+
+/// `--data-urlencode` content: `name=value` and `=value` have the value
+/// encoded, anything else is encoded whole.
+fn urlencode_data(data: &str) -> String {
+    let encode = |text: &str| form_urlencoded::byte_serialize(text.as_bytes()).collect::<String>();
+    match data.split_once('=') {
+        Some(("", value)) => encode(value),
+        Some((name, value)) => format!("{}={}", name, encode(value)),
+        None => encode(data),
+    }
+}
+
+/// A `-F name=value` field. `@path` and `<path` read a file, any `;type=`
+/// or `;filename=` options after the path are dropped.
+fn form_field(field: &str) -> Result<KeyValue> {
+    let (name, value) = field
+        .split_once('=')
+        .ok_or_else(|| eyre!("Invalid form field: {}", field))?;
+    match value.strip_prefix('@').or_else(|| value.strip_prefix('<')) {
+        Some(path) => Ok(KeyValue {
+            file: true,
+            ..KeyValue::new(name, path.split(';').next().unwrap_or_default())
+        }),
+        None => Ok(KeyValue::new(name, value)),
+    }
+}
+
+/// Flags that take a value, so `-XPOST` and `-X POST` can both be handled.
+static SHORT_FLAGS_WITH_VALUE: [char; 13] = [
+    'X', 'H', 'd', 'u', 'A', 'b', 'e', 'F', 'T', 'o', 'm', 'w', 'x',
+];
+
+/// Splits a bundle of short flags like `-sSLk`. A flag that takes a value
+/// ends the bundle, with the rest of the word as its value, or the next
+/// word when nothing is left.
+fn short_flags(word: &str) -> Vec<(String, Option<String>)> {
+    let mut flags = Vec::new();
+    for (i, c) in word.char_indices().skip(1) {
+        if SHORT_FLAGS_WITH_VALUE.contains(&c) {
+            let rest = &word[i + c.len_utf8()..];
+            flags.push((
+                format!("-{}", c),
+                Some(rest.to_string()).filter(|rest| !rest.is_empty()),
+            ));
+            break;
+        }
+        flags.push((format!("-{}", c), None));
+    }
+    flags
+}
+
+/// Builds a request from a curl command line. Covers the flags commonly found
+/// in API docs and "Copy as cURL" output, unknown flags are ignored.
+pub fn parse_curl(command: &str) -> Result<SavedRequest> {
+    let words = shell_words(command)?;
+    let mut words = words.into_iter().peekable();
+    if words.peek().is_some_and(|word| word == "curl") {
+        words.next();
+    }
+
+    let mut method = None;
+    let mut url = None;
+    let mut headers = Vec::new();
+    // Each value along with whether curl would read an `@file` for it,
+    // which `--data-raw` never does.
+    let mut data: Vec<(String, bool)> = Vec::new();
+    let mut form = Vec::new();
+    let mut insecure = false;
+    let mut auth = Auth::None;
+    let mut aws_sigv4 = None;
+    let mut digest = false;
+    // Like curl, redirects are only followed with `-L`.
+    let mut redirects = Redirects {
+        follow: false,
+        ..Default::default()
+    };
+
+    while let Some(word) = words.next() {
+        let flags = if let Some(long) = word.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((flag, value)) => vec![(format!("--{}", flag), Some(value.to_string()))],
+                None => vec![(word.clone(), None)],
+            }
+        } else if word.len() > 2 && word.starts_with('-') {
+            short_flags(&word)
+        } else {
+            vec![(word.clone(), None)]
+        };
+        for (flag, inline_value) in flags {
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| words.next())
+                    .ok_or_else(|| eyre!("{} needs a value", flag))
+            };
+
+            match flag.as_str() {
+                "-X" | "--request" => method = Some(value()?.to_uppercase()),
+                "-H" | "--header" => {
+                    let header = value()?;
+                    let (name, header_value) = header
+                        .split_once(':')
+                        .ok_or_else(|| eyre!("Invalid header: {}", header))?;
+                    headers.push(KeyValue::new(name.trim(), header_value.trim()));
+                }
+                "-d" | "--data" | "--data-binary" | "--data-ascii" => data.push((value()?, true)),
+                "--data-raw" => data.push((value()?, false)),
+                "--data-urlencode" => data.push((urlencode_data(&value()?), false)),
+                "-F" | "--form" => form.push(form_field(&value()?)?),
+                "--form-string" => {
+                    let field = value()?;
+                    let (name, value) = field
+                        .split_once('=')
+                        .ok_or_else(|| eyre!("Invalid form field: {}", field))?;
+                    form.push(KeyValue::new(name, value));
+                }
+                "-u" | "--user" => {
+                    let credentials = value()?;
+                    let (username, password) =
+                        credentials.split_once(':').unwrap_or((&credentials, ""));
+                    auth = Auth::Basic {
+                        username: username.to_string(),
+                        password: password.to_string(),
+                    };
+                }
+                "--aws-sigv4" => aws_sigv4 = Some(value()?),
+                "--digest" => digest = true,
+                "--basic" => digest = false,
+                "-A" | "--user-agent" => set_header(&mut headers, "User-Agent", value()?),
+                "-b" | "--cookie" => set_header(&mut headers, "Cookie", value()?),
+                "-e" | "--referer" => set_header(&mut headers, "Referer", value()?),
+                // The client asks for gzip, deflate and brotli and decodes
+                // them on its own.
+                "--compressed" => {}
+                "-T" | "--upload-file" => {
+                    data = vec![(format!("@{}", value()?), true)];
+                    method.get_or_insert_with(|| "PUT".to_string());
+                }
+                "-k" | "--insecure" => insecure = true,
+                "-L" | "--location" => redirects.follow = true,
+                "--max-redirs" => {
+                    let max_hops = value()?;
+                    // -1 is curl's no limit, the default is kept for it.
+                    if let Ok(max_hops) = max_hops.parse() {
+                        redirects.max_hops = max_hops;
+                    }
+                }
+                "--url" => url = Some(value()?),
+                // Flags that take a value we don't use, skip the value too.
+                "-o" | "--output" | "-m" | "--max-time" | "--connect-timeout" | "-w"
+                | "--write-out" | "--retry" | "-x" | "--proxy" => {
+                    value()?;
+                }
+                flag if flag.starts_with('-') => {}
+                _ => url = Some(word.clone()),
+            }
+        }
+    }
+
+    let url = url.ok_or_else(|| eyre!("No URL found in curl command"))?;
+    if digest && let Auth::Basic { username, password } = auth {
+        auth = Auth::Digest { username, password };
+    }
+    // `--aws-sigv4 aws:amz:region:service` signs with the `-u` credentials.
+    if let Some(provider) = aws_sigv4 {
+        let mut parts = provider.split(':').skip(2);
+        let region = parts.next().unwrap_or_default().to_string();
+        let service = parts.next().unwrap_or_default().to_string();
+        let (access_key, secret_key) = match auth {
+            Auth::Basic { username, password } => (username, password),
+            _ => (String::new(), String::new()),
+        };
+        let session_token = header_value(&headers, "x-amz-security-token")
+            .unwrap_or_default()
+            .to_string();
+        headers.retain(|header| !header.name.eq_ignore_ascii_case("x-amz-security-token"));
+        auth = Auth::AwsSigV4 {
+            access_key,
+            secret_key,
+            session_token,
+            region,
+            service,
+        };
+    }
+    // A single `@file` is sent from disk, as it is by curl.
+    let mut body_file = String::new();
+    if let [(only, true)] = data.as_slice()
+        && let Some(path) = only.strip_prefix('@')
+    {
+        body_file = path.to_string();
+        data.clear();
+    }
+    let body = data
+        .iter()
+        .map(|(value, _)| value.as_str())
+        .collect::<Vec<_>>()
+        .join("&");
+    if !body.is_empty() && header_value(&headers, "content-type").is_none() {
+        headers.push(KeyValue::new(
+            "Content-Type",
+            "application/x-www-form-urlencoded",
+        ));
+    }
+    let body_mode = if !form.is_empty() {
+        BodyMode::Multipart
+    } else if !body_file.is_empty() {
+        BodyMode::File
+    } else {
+        BodyMode::Raw
+    };
+    let method = method.unwrap_or_else(|| {
+        if body_mode == BodyMode::Raw && body.is_empty() {
+            "GET".to_string()
+        } else {
+            "POST".to_string()
+        }
+    });
+    method
+        .parse::<RequestType>()
+        .map_err(|_| eyre!("Unsupported request method: {}", method))?;
+
+    Ok(SavedRequest {
+        method,
+        url,
+        headers,
+        body,
+        insecure,
+        body_mode,
+        form,
+        body_file,
+        auth,
+        redirects,
+        ..Default::default()
+    })
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Renders a request as a curl command that can be pasted into a shell.
+pub fn to_curl(request: &SavedRequest, url: &str) -> String {
+    command(request, &request.auth.apply_to_url(url))
+}
+
+/// The curl command with the auth secrets masked, for showing on screen.
+pub fn to_masked_curl(request: &SavedRequest, url: &str) -> String {
+    let url = request.auth.mask_url(&request.auth.apply_to_url(url));
+    let request = SavedRequest {
+        auth: request.auth.masked(),
+        ..request.clone()
+    };
+    command(&request, &url)
+}
+
+/// The command for a URL that already carries the auth's query parameter.
+fn command(request: &SavedRequest, url: &str) -> String {
+    let mut parts = vec!["curl".to_string()];
+    if request.method != "GET" {
+        parts.push(format!("-X {}", request.method));
+    }
+    parts.push(shell_quote(url));
+    match &request.auth {
+        Auth::Basic { username, password } => {
+            parts.push(format!(
+                "-u {}",
+                shell_quote(&format!("{}:{}", username, password))
+            ));
+        }
+        Auth::Digest { username, password } => {
+            parts.push(format!(
+                "--digest -u {}",
+                shell_quote(&format!("{}:{}", username, password))
+            ));
+        }
+        Auth::Bearer { .. }
+        | Auth::ApiKey {
+            location: ApiKeyLocation::Header,
+            ..
+        } => {
+            if let Some((name, value)) = request.auth.header() {
+                parts.push(format!(
+                    "-H {}",
+                    shell_quote(&format!("{}: {}", name, value))
+                ));
+            }
+        }
+        Auth::AwsSigV4 {
+            access_key,
+            secret_key,
+            session_token,
+            region,
+            service,
+        } => {
+            parts.push(format!(
+                "--aws-sigv4 {}",
+                shell_quote(&format!("aws:amz:{}:{}", region, service))
+            ));
+            parts.push(format!(
+                "-u {}",
+                shell_quote(&format!("{}:{}", access_key, secret_key))
+            ));
+            if !session_token.is_empty() {
+                parts.push(format!(
+                    "-H {}",
+                    shell_quote(&format!("x-amz-security-token: {}", session_token))
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    // Form bodies bring their own Content-Type, like they do when sent.
+    let skip_content_type = request.body_mode != BodyMode::Raw;
+    for header in &request.headers {
+        if header.enabled
+            && !header.name.trim().is_empty()
+            && !(skip_content_type && header.name.trim().eq_ignore_ascii_case("content-type"))
+        {
+            parts.push(format!(
+                "-H {}",
+                shell_quote(&format!("{}: {}", header.name.trim(), header.value))
+            ));
+        }
+    }
+    match request.body_mode {
+        BodyMode::Raw if !request.body.is_empty() => {
+            parts.push(format!("--data-raw {}", shell_quote(&request.body)));
+        }
+        BodyMode::Raw => {}
+        BodyMode::FormUrlencoded => {
+            for field in enabled_fields(&request.form) {
+                let field = format!("{}={}", field.name.trim(), field.value);
+                parts.push(format!("--data-urlencode {}", shell_quote(&field)));
+            }
+        }
+        BodyMode::File => {
+            parts.push(format!(
+                "--data-binary {}",
+                shell_quote(&format!("@{}", request.body_file))
+            ));
+        }
+        BodyMode::Multipart => {
+            for field in enabled_fields(&request.form) {
+                let name = field.name.trim();
+                // `-F` would read a file for a text value starting with @ or <.
+                let flag = if field.file {
+                    format!("-F {}", shell_quote(&format!("{}=@{}", name, field.value)))
+                } else if field.value.starts_with(['@', '<']) {
+                    format!(
+                        "--form-string {}",
+                        shell_quote(&format!("{}={}", name, field.value))
+                    )
+                } else {
+                    format!("-F {}", shell_quote(&format!("{}={}", name, field.value)))
+                };
+                parts.push(flag);
+            }
+        }
+    }
+    if request.insecure {
+        parts.push("-k".to_string());
+    }
+    if request.redirects.follow {
+        parts.push("-L".to_string());
+        if !request.redirects.is_default() {
+            parts.push(format!("--max-redirs {}", request.redirects.max_hops));
+        }
+    }
+
+    parts.join(" \\\n  ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_raw_is_never_a_file() {
+        let request = parse_curl("curl https://example.com --data-raw '@handle'").unwrap();
+        assert!(request.body_mode == BodyMode::Raw);
+        assert_eq!(request.body, "@handle");
+
+        for flag in ["-d", "--data", "--data-binary"] {
+            let request =
+                parse_curl(&format!("curl https://example.com {} @body.json", flag)).unwrap();
+            assert!(request.body_mode == BodyMode::File, "{}", flag);
+            assert_eq!(request.body_file, "body.json");
+        }
+    }
+
+    #[test]
+    fn short_flag_bundles_are_expanded() {
+        let request = parse_curl("curl -sSLk https://example.com").unwrap();
+        assert!(request.insecure);
+        assert!(request.redirects.follow);
+        assert_eq!(request.url, "https://example.com");
+
+        let request = parse_curl("curl -kL https://example.com").unwrap();
+        assert!(request.insecure);
+
+        // A flag taking a value ends the bundle, the value is the rest of
+        // the word or the next word.
+        let request = parse_curl("curl -skXPUT https://example.com").unwrap();
+        assert!(request.insecure);
+        assert_eq!(request.method, "PUT");
+        let request = parse_curl("curl -sX DELETE https://example.com").unwrap();
+        assert_eq!(request.method, "DELETE");
+        let request = parse_curl("curl -so out.json https://example.com").unwrap();
+        assert_eq!(request.url, "https://example.com");
+    }
+
+    #[test]
+    fn preview_command_masks_every_secret() {
+        let auths = [
+            Auth::Basic {
+                username: "user".to_string(),
+                password: "hunter2".to_string(),
+            },
+            Auth::Digest {
+                username: "user".to_string(),
+                password: "hunter2".to_string(),
+            },
+            Auth::Bearer {
+                token: "hunter2".to_string(),
+            },
+            Auth::ApiKey {
+                name: "X-API-Key".to_string(),
+                value: "hunter2".to_string(),
+                location: ApiKeyLocation::Header,
+            },
+            Auth::ApiKey {
+                name: "api_key".to_string(),
+                value: "hunter2".to_string(),
+                location: ApiKeyLocation::Query,
+            },
+            Auth::AwsSigV4 {
+                access_key: "AKIDEXAMPLE".to_string(),
+                secret_key: "hunter2".to_string(),
+                session_token: "hunter2".to_string(),
+                region: "us-east-1".to_string(),
+                service: "s3".to_string(),
+            },
+        ];
+        for auth in auths {
+            let request = SavedRequest {
+                url: "https://example.com/?page=1".to_string(),
+                auth,
+                ..Default::default()
+            };
+            let label = request.auth.label();
+            assert!(
+                to_curl(&request, &request.url).contains("hunter2"),
+                "{}",
+                label
+            );
+
+            let preview = to_masked_curl(&request, &request.url);
+            assert!(!preview.contains("hunter2"), "{}: {}", label, preview);
+            assert!(preview.contains("••••••••"), "{}: {}", label, preview);
+            assert!(!request.auth.summary().unwrap().contains("hunter2"));
+        }
+    }
+
+    #[test]
+    fn compressed_leaves_the_encoding_to_the_client() {
+        let request = parse_curl("curl --compressed https://example.com").unwrap();
+        assert!(request.headers.is_empty());
+    }
+
+    #[test]
+    fn redirects_are_followed_only_with_location() {
+        let request = parse_curl("curl https://example.com").unwrap();
+        assert!(!request.redirects.follow);
+        let request = parse_curl("curl -L https://example.com").unwrap();
+        assert!(request.redirects.follow);
+
+        for redirects in [
+            Redirects {
+                follow: false,
+                ..Default::default()
+            },
+            Redirects::default(),
+            Redirects {
+                follow: true,
+                max_hops: 3,
+            },
+        ] {
+            let request = SavedRequest {
+                method: "GET".to_string(),
+                url: "https://example.com".to_string(),
+                redirects,
+                ..Default::default()
+            };
+            let command = to_curl(&request, &request.url);
+            let parsed = parse_curl(&command).unwrap();
+            assert!(parsed.redirects == redirects, "{}", command);
+        }
+    }
+}
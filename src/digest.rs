@@ -0,0 +1,298 @@
+use color_eyre::Result;
+use md5::Md5;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The hash a challenge asks for. The `-sess` variants hash the client nonce
+/// into the credentials.
+#[derive(Clone, Copy)]
+pub enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Algorithm> {
+        match name.to_ascii_uppercase().as_str() {
+            "MD5" => Some(Algorithm::Md5),
+            "MD5-SESS" => Some(Algorithm::Md5Sess),
+            "SHA-256" => Some(Algorithm::Sha256),
+            "SHA-256-SESS" => Some(Algorithm::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Md5Sess => "MD5-sess",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn is_sess(&self) -> bool {
+        matches!(self, Algorithm::Md5Sess | Algorithm::Sha256Sess)
+    }
+
+    fn hash(&self, data: &str) -> String {
+        match self {
+            Algorithm::Md5 | Algorithm::Md5Sess => hex::encode(Md5::digest(data)),
+            Algorithm::Sha256 | Algorithm::Sha256Sess => hex::encode(Sha256::digest(data)),
+        }
+    }
+}
+
+/// A `WWW-Authenticate: Digest ...` challenge.
+pub struct Challenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: Algorithm,
+    /// Whether the server offered `qop=auth`. Without it the older RFC 2069
+    /// response is used.
+    pub qop_auth: bool,
+}
+
+/// Splits `name=value, name="quoted, value"` parameters.
+fn parameters(text: &str) -> Vec<(String, String)> {
+    let mut parameters = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| *c == ',' || c.is_whitespace()) {
+            chars.next();
+        }
+        let name: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if name.trim().is_empty() {
+            break;
+        }
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                value.push(c);
+            }
+        }
+        parameters.push((name.trim().to_lowercase(), value.trim().to_string()));
+    }
+
+    parameters
+}
+
+impl Challenge {
+    /// Parses one `WWW-Authenticate` value, `None` if it isn't a Digest
+    /// challenge this client can answer.
+    pub fn parse(header: &str) -> Option<Challenge> {
+        let (scheme, rest) = header.trim().split_once(char::is_whitespace)?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+
+        let parameters = parameters(rest);
+        let parameter = |name: &str| {
+            parameters
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let algorithm = match parameter("algorithm") {
+            Some(name) => Algorithm::parse(&name)?,
+            None => Algorithm::Md5,
+        };
+        let qop = parameter("qop");
+        // Only `auth-int` on offer, which would need the body hashed.
+        if qop.as_ref().is_some_and(|qop| {
+            !qop.split(',')
+                .any(|option| option.trim().eq_ignore_ascii_case("auth"))
+        }) {
+            return None;
+        }
+
+        Some(Challenge {
+            realm: parameter("realm").unwrap_or_default(),
+            nonce: parameter("nonce")?,
+            opaque: parameter("opaque"),
+            algorithm,
+            qop_auth: qop.is_some(),
+        })
+    }
+
+    /// The challenge to answer out of a response's `WWW-Authenticate`
+    /// headers, the strongest algorithm first.
+    pub fn pick<'a>(headers: impl Iterator<Item = &'a str>) -> Option<Challenge> {
+        headers
+            .filter_map(Challenge::parse)
+            .max_by_key(|challenge| match challenge.algorithm {
+                Algorithm::Sha256 | Algorithm::Sha256Sess => 1,
+                Algorithm::Md5 | Algorithm::Md5Sess => 0,
+            })
+    }
+
+    /// The `Authorization` header value answering this challenge.
+    /// `uri` is the request target, the path and query.
+    pub fn response(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        // Each challenge is only answered once, so the nonce count is 1.
+        let nc = "00000001";
+        let algorithm = self.algorithm;
+        let mut ha1 = algorithm.hash(&format!("{}:{}:{}", username, self.realm, password));
+        if algorithm.is_sess() {
+            ha1 = algorithm.hash(&format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = algorithm.hash(&format!("{}:{}", method, uri));
+        let response = if self.qop_auth {
+            algorithm.hash(&format!(
+                "{}:{}:{}:{}:auth:{}",
+                ha1, self.nonce, nc, cnonce, ha2
+            ))
+        } else {
+            algorithm.hash(&format!("{}:{}:{}", ha1, self.nonce, ha2))
+        };
+
+        let quote =
+            |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut parts = vec![
+            format!("username={}", quote(username)),
+            format!("realm={}", quote(&self.realm)),
+            format!("uri={}", quote(uri)),
+            format!("algorithm={}", algorithm.name()),
+            format!("nonce={}", quote(&self.nonce)),
+        ];
+        if self.qop_auth {
+            parts.push(format!("nc={}", nc));
+            parts.push(format!("cnonce={}", quote(cnonce)));
+            parts.push("qop=auth".to_string());
+        }
+        parts.push(format!("response={}", quote(&response)));
+        if let Some(opaque) = &self.opaque {
+            parts.push(format!("opaque={}", quote(opaque)));
+        }
+
+        format!("Digest {}", parts.join(", "))
+    }
+}
+
+/// The path and query of a URL, as the request line has them.
+pub fn request_uri(url: &str) -> Result<String> {
+    let url = Url::parse(url)?;
+    Ok(match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    })
+}
+
+/// A client nonce that differs between requests. It only has to be hard to
+/// predict, not secret.
+pub fn cnonce() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seed = format!("{}:{}", now.as_nanos(), std::process::id());
+    hex::encode(&Sha256::digest(seed)[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `response=` value of an `Authorization` header.
+    fn response_value(authorization: &str) -> String {
+        parameters(authorization.trim_start_matches("Digest "))
+            .into_iter()
+            .find(|(name, _)| name == "response")
+            .map(|(_, value)| value)
+            .unwrap()
+    }
+
+    #[test]
+    fn rfc_2617_md5_example() {
+        let challenge = Challenge::parse(
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        )
+        .unwrap();
+        let authorization = challenge.response(
+            "Mufasa",
+            "Circle Of Life",
+            "GET",
+            "/dir/index.html",
+            "0a4f113b",
+        );
+
+        assert_eq!(
+            response_value(&authorization),
+            "6629fae49393a05397450978507c4ef1"
+        );
+        assert!(authorization.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+        assert!(authorization.contains("nc=00000001"));
+        assert!(authorization.contains("qop=auth"));
+    }
+
+    /// RFC 7616 §3.9.1 gives the same request answered with MD5 and SHA-256.
+    fn rfc_7616_response(algorithm: &str) -> String {
+        let challenge = Challenge::parse(&format!(
+            r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm={}, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+            algorithm
+        ))
+        .unwrap();
+        response_value(&challenge.response(
+            "Mufasa",
+            "Circle of Life",
+            "GET",
+            "/dir/index.html",
+            "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+        ))
+    }
+
+    #[test]
+    fn rfc_7616_md5_example() {
+        assert_eq!(rfc_7616_response("MD5"), "8ca523f5e9506fed4657c9700eebdbec");
+    }
+
+    #[test]
+    fn rfc_7616_sha_256_example() {
+        assert_eq!(
+            rfc_7616_response("SHA-256"),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+    }
+
+    #[test]
+    fn sess_hashes_the_nonces_into_the_credentials() {
+        let challenge = Challenge::parse(
+            r#"Digest realm="testrealm@host.com", qop="auth", algorithm=MD5-sess, nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093""#,
+        )
+        .unwrap();
+        let authorization = challenge.response(
+            "Mufasa",
+            "Circle Of Life",
+            "GET",
+            "/dir/index.html",
+            "0a4f113b",
+        );
+
+        // MD5(MD5("Mufasa:testrealm@host.com:Circle Of Life") ":" nonce ":"
+        // cnonce) in place of the plain MD5 HA1 of the RFC 2617 example.
+        assert_eq!(
+            response_value(&authorization),
+            "8e3825c57e897f5a0dec6c2d4e5059d0"
+        );
+        assert!(authorization.contains("algorithm=MD5-sess"));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{
+    http::{BodyMode, KeyValue, SavedRequest},
+    proxy::ProxySettings,
+    storage::{data_dir, workspace_dir},
+    tls::TlsSettings,
+};
+use color_eyre::{Result, eyre::eyre};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+/// A named set of variables, stored as `.pingpong/environments/<name>.json`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Environment {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "TlsSettings::is_default")]
+    pub tls: TlsSettings,
+    #[serde(default, skip_serializing_if = "ProxySettings::is_default")]
+    pub proxy: ProxySettings,
+}
+
+fn environments_dir() -> PathBuf {
+    workspace_dir().join("environments")
+}
+
+fn environment_path(name: &str) -> PathBuf {
+    environments_dir().join(format!("{}.json", name))
+}
+
+/// The proxy and TLS passwords of an environment. Environments are meant to
+/// be committed, so these are kept apart in the user data directory, as
+/// `environment-secrets/<name>.json`.
+#[derive(Serialize, Deserialize, Default)]
+struct EnvironmentSecrets {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    proxy_password: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    tls_password: String,
+}
+
+fn secrets_path(name: &str) -> PathBuf {
+    data_dir()
+        .join("environment-secrets")
+        .join(format!("{}.json", name))
+}
+
+/// Fills in the passwords kept apart from the environment. A password still
+/// in the environment file, from before they were kept apart, wins.
+fn load_secrets(environment: &mut Environment) {
+    let Ok(contents) = fs::read_to_string(secrets_path(&environment.name)) else {
+        return;
+    };
+    let Ok(secrets) = serde_json::from_str::<EnvironmentSecrets>(&contents) else {
+        return;
+    };
+    if environment.proxy.password.is_empty() {
+        environment.proxy.password = secrets.proxy_password;
+    }
+    if environment.tls.password.is_empty() {
+        environment.tls.password = secrets.tls_password;
+    }
+}
+
+fn save_secrets(name: &str, secrets: &EnvironmentSecrets) -> Result<()> {
+    let path = secrets_path(name);
+    if secrets.proxy_password.is_empty() && secrets.tls_password.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(secrets)?)?;
+
+    Ok(())
+}
+
+pub fn load_environments() -> Vec<Environment> {
+    let Ok(entries) = fs::read_dir(environments_dir()) else {
+        return Vec::new();
+    };
+
+    let mut environments: Vec<Environment> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let contents = fs::read_to_string(&path).ok()?;
+            let mut environment: Environment = serde_json::from_str(&contents).ok()?;
+            environment.name = path.file_stem()?.to_string_lossy().to_string();
+            load_secrets(&mut environment);
+            Some(environment)
+        })
+        .collect();
+    environments.sort_by(|a, b| a.name.cmp(&b.name));
+
+    environments
+}
+
+pub fn save_environment(environment: &Environment) -> Result<()> {
+    let name = environment.name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(eyre!("Invalid environment name: {:?}", environment.name));
+    }
+
+    let mut shared = environment.clone();
+    let secrets = EnvironmentSecrets {
+        proxy_password: std::mem::take(&mut shared.proxy.password),
+        tls_password: std::mem::take(&mut shared.tls.password),
+    };
+    save_secrets(name, &secrets)?;
+
+    fs::create_dir_all(environments_dir())?;
+    let mut contents = serde_json::to_string_pretty(&shared)?;
+    contents.push('\n');
+    fs::write(environment_path(name), contents)?;
+
+    Ok(())
+}
+
+pub fn delete_environment(name: &str) -> Result<()> {
+    fs::remove_file(environment_path(name))?;
+    save_secrets(name, &EnvironmentSecrets::default())?;
+    Ok(())
+}
+
+/// Parses the `name=value` form used by the variable prompt.
+pub fn parse_variable(input: &str) -> Result<(String, String)> {
+    let (name, value) = input
+        .split_once('=')
+        .ok_or_else(|| eyre!("Use name=value"))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(eyre!("Variable name can't be empty"));
+    }
+
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Replaces every `{{name}}` placeholder that has a value in `variables`.
+/// Unknown placeholders are left untouched so they're easy to spot.
+pub fn substitute(text: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + end].trim();
+        out.push_str(&rest[..start]);
+        match variables.get(name) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Names of the placeholders in `text` that `variables` can't resolve.
+pub fn unresolved_variables(text: &str, variables: &BTreeMap<String, String>) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + end].trim().to_string();
+        if !variables.contains_key(&name) && !names.contains(&name) {
+            names.push(name);
+        }
+        rest = &rest[start + end + 2..];
+    }
+
+    names
+}
+
+/// Placeholders anywhere in the request that `variables` can't resolve.
+/// Disabled rows and the body the request isn't using aren't sent, so they're
+/// skipped.
+pub fn unresolved_request_variables(
+    request: &SavedRequest,
+    variables: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut texts = vec![request.url.as_str()];
+    let mut rows: Vec<&KeyValue> = request.headers.iter().collect();
+    match request.body_mode {
+        BodyMode::Raw => texts.push(&request.body),
+        BodyMode::FormUrlencoded | BodyMode::Multipart => rows.extend(&request.form),
+        BodyMode::File => texts.push(&request.body_file),
+    }
+    for row in rows.into_iter().filter(|row| row.enabled) {
+        texts.push(&row.name);
+        texts.push(&row.value);
+    }
+    texts.extend(request.auth.texts());
+
+    let mut names = Vec::new();
+    for text in texts {
+        for name in unresolved_variables(text, variables) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+
+    names
+}
+
+pub fn resolve_request(
+    request: &SavedRequest,
+    variables: &BTreeMap<String, String>,
+) -> SavedRequest {
+    let resolve_rows = |rows: &[KeyValue]| {
+        rows.iter()
+            .map(|row| KeyValue {
+                name: substitute(&row.name, variables),
+                value: substitute(&row.value, variables),
+                ..row.clone()
+            })
+            .collect()
+    };
+
+    SavedRequest {
+        url: substitute(&request.url, variables),
+        headers: resolve_rows(&request.headers),
+        body: substitute(&request.body, variables),
+        form: resolve_rows(&request.form),
+        body_file: substitute(&request.body_file, variables),
+        auth: request.auth.resolve(variables),
+        ..request.clone()
+    }
+}
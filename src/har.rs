@@ -0,0 +1,367 @@
+use crate::{
+    collections::{self, collections_dir},
+    history::HistoryEntry,
+    http::{
+        BodyMode, KeyValue, RequestType, SavedRequest, content_type_for, enabled_fields, form_body,
+    },
+    import::ImportSummary,
+};
+use color_eyre::{Result, eyre::eyre};
+use reqwest::{StatusCode, Url};
+use serde_json::{Value, json};
+use std::{fs, path::Path};
+
+/// Request headers that describe the original connection rather than the
+/// request, and would be wrong if copied into a replay.
+static SKIPPED_HEADERS: [&str; 3] = ["content-length", "host", "connection"];
+
+/// Imports every entry of a HAR archive as a saved request in a new
+/// collection named after the file. Entries are numbered so they keep the
+/// order they were captured in.
+pub fn import_har(document: Value, name: &str) -> Result<ImportSummary> {
+    let entries = document["log"]["entries"]
+        .as_array()
+        .ok_or_else(|| eyre!("Not a HAR file"))?;
+
+    let dir = collections::unique_folder_path(&collections_dir(), name);
+    fs::create_dir_all(&dir)?;
+    let mut summary = ImportSummary {
+        collection: collections::request_location(&dir),
+        requests: 0,
+        skipped: Vec::new(),
+        stripped: Vec::new(),
+        environment: None,
+    };
+
+    for (index, entry) in entries.iter().enumerate() {
+        let request = &entry["request"];
+        let method = request["method"].as_str().unwrap_or("GET").to_uppercase();
+        let url = request["url"].as_str().unwrap_or_default();
+        let name = format!("{:03} {} {}", index + 1, method, short_name(url));
+        if method.parse::<RequestType>().is_err() {
+            summary
+                .skipped
+                .push(format!("{}: unsupported method {}", name, method));
+            continue;
+        }
+
+        let mut saved = SavedRequest {
+            method,
+            url: url.to_string(),
+            headers: request_headers(request),
+            ..Default::default()
+        };
+        add_post_data(request, &mut saved);
+        let stripped =
+            collections::save_request(&collections::unique_request_path(&dir, &name), &saved)?;
+        summary.record_stripped(&name, stripped);
+        summary.requests += 1;
+    }
+
+    Ok(summary)
+}
+
+/// The path of a URL without the leading slash, or the host for the root.
+fn short_name(url: &str) -> String {
+    let Ok(url) = Url::parse(url) else {
+        return url.to_string();
+    };
+    match url.path().trim_matches('/') {
+        "" => url.host_str().unwrap_or_default().to_string(),
+        path => path.replace('/', "-"),
+    }
+}
+
+/// Request headers in capture order. HTTP/2 captures can split cookies over
+/// several headers, they're joined back into one as HTTP/1.1 requires.
+fn request_headers(request: &Value) -> Vec<KeyValue> {
+    let mut headers: Vec<KeyValue> = Vec::new();
+    for header in request["headers"].as_array().into_iter().flatten() {
+        let (Some(name), Some(value)) = (header["name"].as_str(), header["value"].as_str()) else {
+            continue;
+        };
+        // HTTP/2 pseudo headers like `:authority` show up in Chrome exports.
+        let lower = name.to_lowercase();
+        if name.starts_with(':') || SKIPPED_HEADERS.contains(&lower.as_str()) {
+            continue;
+        }
+        match headers
+            .iter_mut()
+            .find(|h| h.name.eq_ignore_ascii_case("cookie"))
+        {
+            Some(cookie) if lower == "cookie" => {
+                cookie.value.push_str("; ");
+                cookie.value.push_str(value);
+            }
+            _ => headers.push(KeyValue::new(name, value)),
+        }
+    }
+
+    headers
+}
+
+/// Uses the captured body text when there is one. Otherwise the body is
+/// rebuilt as a form from its params, multipart if it was one.
+fn add_post_data(request: &Value, saved: &mut SavedRequest) {
+    let post_data = &request["postData"];
+    if let Some(text) = post_data["text"].as_str() {
+        saved.body = text.to_string();
+        return;
+    }
+    let Some(params) = post_data["params"].as_array() else {
+        return;
+    };
+
+    let multipart = post_data["mimeType"]
+        .as_str()
+        .is_some_and(|mime_type| mime_type.starts_with("multipart/form-data"));
+    saved.body_mode = if multipart {
+        BodyMode::Multipart
+    } else {
+        BodyMode::FormUrlencoded
+    };
+    saved.form = params
+        .iter()
+        .map(|param| {
+            let name = param["name"].as_str().unwrap_or_default();
+            match param["fileName"].as_str() {
+                Some(file_name) if multipart => KeyValue {
+                    file: true,
+                    ..KeyValue::new(name, file_name)
+                },
+                _ => KeyValue::new(name, param["value"].as_str().unwrap_or_default()),
+            }
+        })
+        .collect();
+}
+
+fn name_values<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> Vec<Value> {
+    pairs
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The URL and headers of an entry recorded before what was sent was kept,
+/// rebuilt from the request as edited. The auth went out with the request, so
+/// it's part of what was sent, masked like in `SentRequest`.
+fn unresolved_request(request: &SavedRequest) -> (String, Vec<(String, String)>) {
+    let auth = &request.auth;
+    let url = auth.mask_url(&auth.apply_to_url(&request.url));
+    let mut headers: Vec<(String, String)> = request
+        .headers
+        .iter()
+        .filter(|header| header.enabled && !header.name.trim().is_empty())
+        .map(|header| (header.name.trim().to_string(), header.value.clone()))
+        .collect();
+    if let Some((name, value)) = auth.header() {
+        headers.retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+        headers.push((name.clone(), value));
+    }
+    for (name, value) in &mut headers {
+        *value = auth.mask_header(name, value);
+    }
+
+    (url, headers)
+}
+
+fn har_entry(entry: &HistoryEntry) -> Value {
+    let request = &entry.request;
+    let response = &entry.response;
+    let (url, request_headers) = match &entry.sent {
+        Some(sent) => (sent.url.clone(), sent.headers.clone()),
+        None => unresolved_request(request),
+    };
+    let query: Vec<(String, String)> = Url::parse(&url)
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+    let http_version = if response.http_version.is_empty() {
+        "HTTP/1.1"
+    } else {
+        &response.http_version
+    };
+
+    let post_data = match request.body_mode {
+        BodyMode::Raw if request.body.is_empty() => None,
+        BodyMode::Raw => Some(json!({
+            "mimeType": header_value(&request_headers, "content-type").unwrap_or_default(),
+            "text": request.body,
+        })),
+        BodyMode::FormUrlencoded => Some(json!({
+            "mimeType": "application/x-www-form-urlencoded",
+            "params": name_values(
+                enabled_fields(&request.form).map(|f| (f.name.trim(), f.value.as_str()))
+            ),
+            "text": form_body(&request.form),
+        })),
+        // The file isn't kept, only where it was sent from.
+        BodyMode::File => Some(json!({
+            "mimeType": header_value(&request_headers, "content-type")
+                .map(str::to_string)
+                .unwrap_or_else(|| content_type_for(Path::new(&request.body_file))),
+            "comment": format!("Sent from {}", request.body_file),
+        })),
+        // The parts aren't kept, so there's no text, only the fields.
+        BodyMode::Multipart => Some(json!({
+            "mimeType": "multipart/form-data",
+            "params": enabled_fields(&request.form)
+                .map(|field| {
+                    if field.file {
+                        json!({ "name": field.name.trim(), "fileName": field.value })
+                    } else {
+                        json!({ "name": field.name.trim(), "value": field.value })
+                    }
+                })
+                .collect::<Vec<_>>(),
+        })),
+    };
+
+    // HAR uses -1 for sizes that aren't known.
+    let body_size = match &post_data {
+        Some(post_data) => post_data["text"]
+            .as_str()
+            .map(|text| text.len() as i64)
+            .unwrap_or(-1),
+        None => 0,
+    };
+
+    let mut har_request = json!({
+        "method": request.method,
+        "url": url,
+        "httpVersion": http_version,
+        "cookies": [],
+        "headers": name_values(request_headers.iter().map(|(n, v)| (n.as_str(), v.as_str()))),
+        "queryString": name_values(query.iter().map(|(n, v)| (n.as_str(), v.as_str()))),
+        "headersSize": -1,
+        "bodySize": body_size,
+    });
+    if let Some(post_data) = post_data {
+        har_request["postData"] = post_data;
+    }
+
+    let status = response.status.unwrap_or(0);
+    let status_text = match &response.error {
+        Some(error) => error.clone(),
+        None => StatusCode::from_u16(status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    let wait = response.wait_ms.min(response.elapsed_ms);
+
+    json!({
+        "startedDateTime": entry.sent_at.to_rfc3339(),
+        "time": response.elapsed_ms,
+        "request": har_request,
+        "response": {
+            "status": status,
+            "statusText": status_text,
+            "httpVersion": http_version,
+            "cookies": [],
+            "headers": name_values(
+                response.headers.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+            ),
+            "content": {
+                "size": response.size,
+                "mimeType": header_value(&response.headers, "content-type").unwrap_or_default(),
+                "text": response.body,
+            },
+            "redirectURL": header_value(&response.headers, "location").unwrap_or_default(),
+            "headersSize": -1,
+            "bodySize": response.size,
+        },
+        "cache": {},
+        "timings": {
+            "send": 0,
+            "wait": wait,
+            "receive": response.elapsed_ms - wait,
+        },
+    })
+}
+
+/// Writes history entries as a HAR 1.2 archive. Only the total time and the
+/// time to first byte are measured, so they're reported as `wait` and
+/// `receive` timings.
+pub fn export_har(entries: &[&HistoryEntry], path: &Path) -> Result<()> {
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "PingPong",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries.iter().map(|entry| har_entry(entry)).collect::<Vec<_>>(),
+        }
+    });
+    let mut contents = serde_json::to_string_pretty(&har)?;
+    contents.push('\n');
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::Auth,
+        environment::resolve_request,
+        history::{ResponseSummary, SentRequest},
+        http::build_headers,
+    };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn templated_request_is_exported_resolved_and_masked() {
+        let request = SavedRequest {
+            method: "GET".to_string(),
+            url: "{{base_url}}/users?page=1".to_string(),
+            headers: vec![KeyValue::new("X-Trace", "{{trace}}")],
+            auth: Auth::Bearer {
+                token: "{{token}}".to_string(),
+            },
+            ..Default::default()
+        };
+        let variables = BTreeMap::from([
+            ("base_url".to_string(), "http://api.test".to_string()),
+            ("trace".to_string(), "abc".to_string()),
+            ("token".to_string(), "s3cret".to_string()),
+        ]);
+
+        let resolved = resolve_request(&request, &variables);
+        let mut headers = build_headers(&resolved.headers).unwrap();
+        let mut url = resolved.url.clone();
+        resolved.auth.apply(&mut url, &mut headers).unwrap();
+        let entry = HistoryEntry {
+            sent_at: chrono::Local::now(),
+            request,
+            sent: Some(SentRequest::new(&url, &headers, &resolved.auth)),
+            response: ResponseSummary::from_error("refused".to_string(), 0),
+        };
+
+        let har = har_entry(&entry);
+        assert_eq!(har["request"]["url"], "http://api.test/users?page=1");
+        assert_eq!(
+            har["request"]["queryString"],
+            json!([{ "name": "page", "value": "1" }])
+        );
+        let headers = &har["request"]["headers"];
+        assert!(
+            headers
+                .as_array()
+                .unwrap()
+                .contains(&json!({ "name": "x-trace", "value": "abc" }))
+        );
+        let har = har.to_string();
+        assert!(har.contains("Bearer •"));
+        assert!(!har.contains("s3cret"));
+        assert!(!har.contains("{{"));
+    }
+}
@@ -0,0 +1,298 @@
+use ratatui::prelude::*;
+
+const INDENT: &str = "  ";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContentKind {
+    Json,
+    Xml,
+    Html,
+    Text,
+}
+
+impl ContentKind {
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        let Some(content_type) = content_type else {
+            return ContentKind::Text;
+        };
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        if mime == "application/json" || mime.ends_with("+json") {
+            ContentKind::Json
+        } else if mime == "text/html" || mime == "application/xhtml+xml" {
+            ContentKind::Html
+        } else if mime == "application/xml" || mime == "text/xml" || mime.ends_with("+xml") {
+            ContentKind::Xml
+        } else {
+            ContentKind::Text
+        }
+    }
+}
+
+/// Formats a response body for display, falling back to the raw text whenever
+/// the body can't be pretty printed as the given kind.
+pub fn format_body(body: &str, kind: ContentKind) -> Vec<Line<'static>> {
+    let pretty = match kind {
+        ContentKind::Json => format_json(body),
+        ContentKind::Xml => format_markup(body, false),
+        ContentKind::Html => format_markup(body, true),
+        ContentKind::Text => None,
+    };
+
+    pretty.unwrap_or_else(|| raw_lines(body))
+}
+
+pub fn raw_lines(body: &str) -> Vec<Line<'static>> {
+    body.lines()
+        .map(|line| Line::styled(line.to_string(), Style::default().fg(Color::White)))
+        .collect()
+}
+
+#[derive(PartialEq)]
+enum JsonToken<'a> {
+    Open(char),
+    Close(char),
+    Colon,
+    Comma,
+    String(&'a str),
+    Number(&'a str),
+    Literal(&'a str),
+}
+
+fn tokenize_json(input: &str) -> Option<Vec<JsonToken<'_>>> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                i += 1;
+                continue;
+            }
+            b'{' | b'[' => tokens.push(JsonToken::Open(bytes[i] as char)),
+            b'}' | b']' => tokens.push(JsonToken::Close(bytes[i] as char)),
+            b':' => tokens.push(JsonToken::Colon),
+            b',' => tokens.push(JsonToken::Comma),
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if i >= bytes.len() {
+                    return None;
+                }
+                tokens.push(JsonToken::String(&input[start..=i]));
+            }
+            b'-' | b'0'..=b'9' => {
+                while i + 1 < bytes.len()
+                    && matches!(bytes[i + 1], b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')
+                {
+                    i += 1;
+                }
+                tokens.push(JsonToken::Number(&input[start..=i]));
+            }
+            b't' | b'f' | b'n' => {
+                while i + 1 < bytes.len() && bytes[i + 1].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                tokens.push(JsonToken::Literal(&input[start..=i]));
+            }
+            _ => return None,
+        }
+        i += 1;
+    }
+
+    Some(tokens)
+}
+
+fn format_json(body: &str) -> Option<Vec<Line<'static>>> {
+    serde_json::from_str::<serde::de::IgnoredAny>(body).ok()?;
+    let tokens = tokenize_json(body)?;
+
+    let punctuation = Style::default().fg(Color::Gray);
+    let mut lines = Vec::new();
+    let mut line: Vec<Span<'static>> = Vec::new();
+    let mut depth = 0;
+    let mut iter = tokens.iter().peekable();
+
+    while let Some(token) = iter.next() {
+        match token {
+            JsonToken::Open(open) => {
+                let close = if *open == '{' { '}' } else { ']' };
+                if iter.peek() == Some(&&JsonToken::Close(close)) {
+                    iter.next();
+                    line.push(Span::styled(format!("{}{}", open, close), punctuation));
+                } else {
+                    line.push(Span::styled(open.to_string(), punctuation));
+                    depth += 1;
+                    lines.push(Line::from(std::mem::take(&mut line)));
+                    line.push(Span::raw(INDENT.repeat(depth)));
+                }
+            }
+            JsonToken::Close(close) => {
+                depth -= 1;
+                lines.push(Line::from(std::mem::take(&mut line)));
+                line.push(Span::raw(INDENT.repeat(depth)));
+                line.push(Span::styled(close.to_string(), punctuation));
+            }
+            JsonToken::Colon => line.push(Span::styled(": ", punctuation)),
+            JsonToken::Comma => {
+                line.push(Span::styled(",", punctuation));
+                lines.push(Line::from(std::mem::take(&mut line)));
+                line.push(Span::raw(INDENT.repeat(depth)));
+            }
+            JsonToken::String(value) => {
+                let color = if iter.peek() == Some(&&JsonToken::Colon) {
+                    Color::LightCyan
+                } else {
+                    Color::Green
+                };
+                line.push(Span::styled(value.to_string(), Style::default().fg(color)));
+            }
+            JsonToken::Number(value) => {
+                line.push(Span::styled(
+                    value.to_string(),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            JsonToken::Literal(value) => {
+                let color = if *value == "null" {
+                    Color::DarkGray
+                } else {
+                    Color::Magenta
+                };
+                line.push(Span::styled(value.to_string(), Style::default().fg(color)));
+            }
+        }
+    }
+    lines.push(Line::from(line));
+
+    Some(lines)
+}
+
+static HTML_VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+fn format_markup(body: &str, html: bool) -> Option<Vec<Line<'static>>> {
+    let mut lines = Vec::new();
+    let mut depth: usize = 0;
+    let mut rest = body;
+
+    while !rest.is_empty() {
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            for comment in rest[..end].lines() {
+                lines.push(Line::styled(
+                    format!("{}{}", INDENT.repeat(depth), comment.trim()),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            rest = &rest[end..];
+        } else if rest.starts_with('<') {
+            let end = rest.find('>')? + 1;
+            let tag = &rest[..end];
+            rest = &rest[end..];
+
+            let is_closing = tag.starts_with("</");
+            let is_declaration = tag.starts_with("<?") || tag.starts_with("<!");
+            let name = tag
+                .trim_start_matches(['<', '/'])
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            let is_void = tag.ends_with("/>")
+                || is_declaration
+                || (html && HTML_VOID_ELEMENTS.contains(&name.as_str()));
+
+            if is_closing {
+                depth = depth.saturating_sub(1);
+            }
+            let mut line = vec![Span::raw(INDENT.repeat(depth))];
+            line.extend(highlight_tag(tag));
+            lines.push(Line::from(line));
+            if !is_closing && !is_void {
+                depth += 1;
+            }
+
+            // Keep script and style contents as they are, they aren't markup.
+            if html && !is_closing && (name == "script" || name == "style") {
+                let closing_tag = format!("</{}", name);
+                let end = rest.to_ascii_lowercase().find(&closing_tag)?;
+                for text in rest[..end].lines().filter(|l| !l.trim().is_empty()) {
+                    lines.push(Line::styled(
+                        format!("{}{}", INDENT.repeat(depth), text.trim()),
+                        Style::default().fg(Color::White),
+                    ));
+                }
+                rest = &rest[end..];
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            for text in rest[..end].lines().filter(|l| !l.trim().is_empty()) {
+                lines.push(Line::styled(
+                    format!("{}{}", INDENT.repeat(depth), text.trim()),
+                    Style::default().fg(Color::White),
+                ));
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    Some(lines)
+}
+
+fn highlight_tag(tag: &str) -> Vec<Span<'static>> {
+    let tag_style = Style::default().fg(Color::LightBlue);
+    let prefix = if tag.starts_with("</") { 2 } else { 1 };
+    let name_end = tag[prefix..]
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .map(|i| i + prefix)
+        .unwrap_or(tag.len());
+    let mut spans = vec![Span::styled(tag[..name_end].to_string(), tag_style)];
+
+    let mut rest = &tag[name_end..];
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix(['"', '\'']) {
+            let quote = rest.chars().next().unwrap_or('"');
+            let end = quoted.find(quote).map(|i| i + 2).unwrap_or(rest.len());
+            spans.push(Span::styled(
+                rest[..end].to_string(),
+                Style::default().fg(Color::Green),
+            ));
+            rest = &rest[end..];
+        } else if rest.starts_with(|c: char| c.is_alphanumeric()) {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == ':'))
+                .unwrap_or(rest.len());
+            spans.push(Span::styled(
+                rest[..end].to_string(),
+                Style::default().fg(Color::LightCyan),
+            ));
+            rest = &rest[end..];
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            let style = if ch == '=' {
+                Style::default().fg(Color::Gray)
+            } else {
+                tag_style
+            };
+            spans.push(Span::styled(ch.to_string(), style));
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+
+    spans
+}
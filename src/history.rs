@@ -0,0 +1,179 @@
+use crate::{
+    auth::Auth,
+    http::{HttpResponse, SavedRequest},
+    storage::data_dir,
+};
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+static MAX_HISTORY_ENTRIES: usize = 500;
+static MAX_STORED_BODY_BYTES: usize = 64 * 1024;
+
+/// What we remember about a response once it has been replaced in the Response
+/// panel. Bodies are capped so the history file stays small.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ResponseSummary {
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub http_version: String,
+    pub elapsed_ms: u64,
+    #[serde(default)]
+    pub wait_ms: u64,
+    pub size: usize,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ResponseSummary {
+    pub fn from_response(response: &HttpResponse) -> Self {
+        let body = response.body_text();
+        let body = if body.len() > MAX_STORED_BODY_BYTES {
+            let mut end = MAX_STORED_BODY_BYTES;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body[..end].to_string()
+        } else {
+            body
+        };
+
+        Self {
+            status: Some(response.status.as_u16()),
+            error: None,
+            http_version: format!("{:?}", response.version),
+            elapsed_ms: response.elapsed.as_millis() as u64,
+            wait_ms: response.wait.as_millis() as u64,
+            size: response.size(),
+            headers: response.headers.clone(),
+            body,
+        }
+    }
+
+    pub fn from_error(error: String, elapsed_ms: u64) -> Self {
+        Self {
+            status: None,
+            error: Some(error),
+            http_version: String::new(),
+            elapsed_ms,
+            wait_ms: 0,
+            size: 0,
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    pub fn status_text(&self) -> String {
+        match self.status {
+            Some(status) => status.to_string(),
+            None => "ERR".to_string(),
+        }
+    }
+}
+
+/// The URL and headers a request went out with, variables resolved and auth
+/// added, with the credentials masked.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SentRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl SentRequest {
+    pub fn new(url: &str, headers: &HeaderMap, auth: &Auth) -> Self {
+        Self {
+            url: auth.mask_url(url),
+            headers: headers
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes());
+                    (name.to_string(), auth.mask_header(name.as_str(), &value))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub sent_at: DateTime<Local>,
+    /// The request as edited, placeholders kept, without auth secrets typed
+    /// in directly.
+    pub request: SavedRequest,
+    /// Missing from entries recorded before it was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent: Option<SentRequest>,
+    pub response: ResponseSummary,
+}
+
+impl HistoryEntry {
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        let haystack = format!(
+            "{} {} {}",
+            self.request.method,
+            self.request.url,
+            self.response.status_text()
+        )
+        .to_lowercase();
+        filter
+            .split_whitespace()
+            .all(|term| haystack.contains(term))
+    }
+}
+
+fn history_path() -> PathBuf {
+    data_dir().join("history.jsonl")
+}
+
+/// Loads the most recent history entries, oldest first. Lines that can't be
+/// parsed are skipped rather than failing the whole load.
+pub fn load_history() -> Vec<HistoryEntry> {
+    let Ok(file) = fs::File::open(history_path()) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<HistoryEntry> = BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    if entries.len() > MAX_HISTORY_ENTRIES {
+        entries.drain(..entries.len() - MAX_HISTORY_ENTRIES);
+        let _ = rewrite_history(&entries);
+    }
+
+    entries
+}
+
+pub fn append_history(entry: &HistoryEntry) -> Result<()> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+
+    Ok(())
+}
+
+fn rewrite_history(entries: &[HistoryEntry]) -> Result<()> {
+    let mut contents = String::new();
+    for entry in entries {
+        contents.push_str(&serde_json::to_string(entry)?);
+        contents.push('\n');
+    }
+    fs::write(history_path(), contents)?;
+
+    Ok(())
+}
@@ -0,0 +1,1052 @@
+use crate::{
+    auth::{ApiKeyLocation, Auth},
+    cookies::CookieJar,
+    proxy::ProxySettings,
+    storage::expand_home,
+    tls::TlsConfig,
+    tls_info::TlsDetails,
+};
+use color_eyre::{Result, eyre::eyre};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use reqwest::{
+    Client, Method, StatusCode, Url, Version,
+    header::{
+        AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, HeaderMap, HeaderName, HeaderValue,
+        LOCATION, PROXY_AUTHORIZATION, SET_COOKIE,
+    },
+    multipart::{Form, Part},
+    redirect::Policy,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use strum_macros::{EnumIter, EnumString};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, EnumIter, EnumString)]
+pub enum RequestType {
+    GET,
+    POST,
+    PUT,
+    PATCH,
+    DELETE,
+    HEAD,
+    OPTIONS,
+    TRACE,
+}
+
+impl fmt::Display for RequestType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let request_type = match self {
+            RequestType::GET => "GET",
+            RequestType::POST => "POST",
+            RequestType::PUT => "PUT",
+            RequestType::PATCH => "PATCH",
+            RequestType::DELETE => "DELETE",
+            RequestType::HEAD => "HEAD",
+            RequestType::OPTIONS => "OPTIONS",
+            RequestType::TRACE => "TRACE",
+        };
+        write!(f, "{}", request_type)
+    }
+}
+
+impl RequestType {
+    pub fn method(self) -> Method {
+        match self {
+            RequestType::GET => Method::GET,
+            RequestType::POST => Method::POST,
+            RequestType::PUT => Method::PUT,
+            RequestType::PATCH => Method::PATCH,
+            RequestType::DELETE => Method::DELETE,
+            RequestType::HEAD => Method::HEAD,
+            RequestType::OPTIONS => Method::OPTIONS,
+            RequestType::TRACE => Method::TRACE,
+        }
+    }
+}
+
+pub fn parse_into_https(url: &str) -> String {
+    if url.starts_with("http") || url.starts_with("https") {
+        return url.to_string();
+    }
+
+    if url.contains("localhost") {
+        return format!("http://{}", url);
+    }
+
+    format!("https://{}", url)
+}
+
+/// One row of the header, query parameter or form table. Rows can be
+/// switched off without deleting them, and the same name can appear more than
+/// once.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct KeyValue {
+    pub name: String,
+    pub value: String,
+    #[serde(default = "enabled_by_default", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// Multipart fields only: `value` is the path of a file to send as the
+    /// part's content.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub file: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+impl KeyValue {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            enabled: true,
+            file: false,
+        }
+    }
+
+    /// Why the row can't be sent as a header, if it can't. Rows without a
+    /// name are ignored rather than treated as errors.
+    pub fn header_error(&self) -> Option<String> {
+        if self.name.trim().is_empty() {
+            return None;
+        }
+        if HeaderName::from_str(self.name.trim()).is_err() {
+            return Some(format!("{:?} is not a valid header name", self.name));
+        }
+        if HeaderValue::from_str(&self.value).is_err() {
+            return Some(format!(
+                "The value of {} is not a valid header value",
+                self.name
+            ));
+        }
+
+        None
+    }
+}
+
+/// The value of the first enabled header called `name`, ignoring case.
+pub fn header_value<'a>(headers: &'a [KeyValue], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|header| header.enabled && header.name.trim().eq_ignore_ascii_case(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Parses headers written as a JSON object, the way they used to be stored.
+pub fn headers_from_json(text: &str) -> Result<Vec<KeyValue>> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let map: serde_json::Map<String, Value> = serde_json::from_str(text)?;
+
+    Ok(map
+        .into_iter()
+        .map(|(name, value)| match value {
+            Value::String(value) => KeyValue::new(name, value),
+            value => KeyValue::new(name, value.to_string()),
+        })
+        .collect())
+}
+
+/// Reads headers as table rows, or as the JSON object string older saved
+/// requests and history entries have.
+fn deserialize_headers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<KeyValue>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredHeaders {
+        Rows(Vec<KeyValue>),
+        Json(String),
+    }
+
+    Ok(match StoredHeaders::deserialize(deserializer)? {
+        StoredHeaders::Rows(rows) => rows,
+        StoredHeaders::Json(text) => headers_from_json(&text).unwrap_or_default(),
+    })
+}
+
+pub fn build_headers(headers: &[KeyValue]) -> Result<HeaderMap> {
+    let mut out = HeaderMap::new();
+    for header in headers
+        .iter()
+        .filter(|h| h.enabled && !h.name.trim().is_empty())
+    {
+        if let Some(error) = header.header_error() {
+            return Err(eyre!(error));
+        }
+        out.append(
+            HeaderName::from_str(header.name.trim())?,
+            HeaderValue::from_str(&header.value)?,
+        );
+    }
+
+    Ok(out)
+}
+
+/// Characters escaped in query parameter names and values. Braces are left
+/// alone so `{{var}}` placeholders stay readable.
+const QUERY_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>')
+    .add(b'`');
+
+/// Splits a URL into the part before the query, the query and the fragment
+/// (with its `#`). The URL doesn't have to parse, it may still contain
+/// placeholders.
+fn split_query(url: &str) -> (&str, &str, &str) {
+    let (rest, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+    match rest.split_once('?') {
+        Some((base, query)) => (base, query, fragment),
+        None => (rest, "", fragment),
+    }
+}
+
+fn decode_query_component(text: &str) -> String {
+    percent_decode_str(&text.replace('+', " "))
+        .decode_utf8_lossy()
+        .to_string()
+}
+
+/// The query parameters of a URL, decoded, in the order they appear.
+pub fn query_params(url: &str) -> Vec<KeyValue> {
+    let (_, query, _) = split_query(url);
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            KeyValue::new(decode_query_component(name), decode_query_component(value))
+        })
+        .collect()
+}
+
+/// `url` with its query replaced by the enabled parameters, encoded. A
+/// parameter without a value is written as just its name.
+pub fn with_query_params(url: &str, params: &[KeyValue]) -> String {
+    let (base, _, fragment) = split_query(url);
+    let query: Vec<String> = params
+        .iter()
+        .filter(|param| param.enabled && !(param.name.is_empty() && param.value.is_empty()))
+        .map(|param| {
+            let name = utf8_percent_encode(&param.name, QUERY_ENCODE_SET);
+            if param.value.is_empty() {
+                name.to_string()
+            } else {
+                format!(
+                    "{}={}",
+                    name,
+                    utf8_percent_encode(&param.value, QUERY_ENCODE_SET)
+                )
+            }
+        })
+        .collect();
+
+    if query.is_empty() {
+        format!("{}{}", base, fragment)
+    } else {
+        format!("{}?{}{}", base, query.join("&"), fragment)
+    }
+}
+
+/// How the body of a request is written in the editor.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum BodyMode {
+    /// The body is sent exactly as typed.
+    #[default]
+    Raw,
+    /// A table of fields sent as `application/x-www-form-urlencoded`.
+    FormUrlencoded,
+    /// A table of text and file fields sent as `multipart/form-data`.
+    Multipart,
+    /// A file streamed from disk as is.
+    File,
+}
+
+impl BodyMode {
+    pub fn next(self) -> Self {
+        match self {
+            BodyMode::Raw => BodyMode::FormUrlencoded,
+            BodyMode::FormUrlencoded => BodyMode::Multipart,
+            BodyMode::Multipart => BodyMode::File,
+            BodyMode::File => BodyMode::Raw,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BodyMode::Raw => "raw",
+            BodyMode::FormUrlencoded => "form-urlencoded",
+            BodyMode::Multipart => "multipart",
+            BodyMode::File => "file",
+        }
+    }
+
+    fn is_raw(&self) -> bool {
+        *self == BodyMode::Raw
+    }
+}
+
+/// Encodes the enabled fields as an `application/x-www-form-urlencoded` body.
+pub fn form_body(fields: &[KeyValue]) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(
+            enabled_fields(fields).map(|field| (field.name.as_str(), field.value.as_str())),
+        )
+        .finish()
+}
+
+/// The fields of a form that will be sent: enabled and named.
+pub fn enabled_fields(fields: &[KeyValue]) -> impl Iterator<Item = &KeyValue> {
+    fields
+        .iter()
+        .filter(|field| field.enabled && !field.name.trim().is_empty())
+}
+
+/// What gets sent as the request body.
+#[derive(Clone)]
+pub enum RequestBody {
+    Text(String),
+    /// Multipart fields. Files are read when the request is sent, reqwest
+    /// generates the boundary and the Content-Type that goes with it.
+    Multipart(Vec<KeyValue>),
+    /// A file that's streamed rather than read into memory.
+    File(PathBuf),
+}
+
+/// The Content-Type for a file, guessed from its extension.
+pub fn content_type_for(path: &Path) -> String {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .to_string()
+}
+
+/// The body to send for `request`, `None` if there's nothing to send or the
+/// method doesn't take a body. Form bodies replace any Content-Type header
+/// with the one that matches them, file bodies only add one if there's none.
+pub fn build_body(request: &SavedRequest, headers: &mut HeaderMap) -> Result<Option<RequestBody>> {
+    let request_type = request.method.parse::<RequestType>()?;
+    if matches!(
+        request_type,
+        RequestType::GET | RequestType::HEAD | RequestType::TRACE
+    ) {
+        return Ok(None);
+    }
+
+    match request.body_mode {
+        BodyMode::Raw if request.body.is_empty() => Ok(None),
+        BodyMode::Raw => Ok(Some(RequestBody::Text(request.body.clone()))),
+        BodyMode::FormUrlencoded => {
+            if let Some(field) = enabled_fields(&request.form).find(|field| field.file) {
+                return Err(eyre!(
+                    "{} is a file, files can only be sent in a multipart body",
+                    field.name
+                ));
+            }
+            headers.insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+            Ok(Some(RequestBody::Text(form_body(&request.form))))
+        }
+        BodyMode::Multipart => {
+            headers.remove(CONTENT_TYPE);
+            Ok(Some(RequestBody::Multipart(
+                enabled_fields(&request.form).cloned().collect(),
+            )))
+        }
+        BodyMode::File if request.body_file.trim().is_empty() => {
+            Err(eyre!("No file picked to send"))
+        }
+        BodyMode::File => {
+            let path = expand_home(request.body_file.trim());
+            if !headers.contains_key(CONTENT_TYPE) {
+                headers.insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_str(&content_type_for(&path))?,
+                );
+            }
+            Ok(Some(RequestBody::File(path)))
+        }
+    }
+}
+
+async fn multipart_form(fields: Vec<KeyValue>) -> Result<Form, String> {
+    let mut form = Form::new();
+    for field in fields {
+        if !field.file {
+            form = form.text(field.name, field.value);
+            continue;
+        }
+
+        let path = expand_home(&field.value);
+        let contents = tokio::fs::read(&path)
+            .await
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let mut part = Part::bytes(contents)
+            .mime_str(&content_type_for(&path))
+            .map_err(|e| e.to_string())?;
+        if let Some(file_name) = path.file_name() {
+            part = part.file_name(file_name.to_string_lossy().to_string());
+        }
+        form = form.part(field.name, part);
+    }
+
+    Ok(form)
+}
+
+/// Everything needed to fire a single request, detached from the UI state so it
+/// can be moved onto a background task.
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub request_type: RequestType,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<RequestBody>,
+    /// The jar cookies are sent from and stored in, `None` when the request
+    /// doesn't use cookies.
+    pub cookies: Option<CookieJar>,
+    pub redirects: Redirects,
+    /// Set by `Auth::send`, left out on a redirect to another host.
+    pub added_auth: AddedAuth,
+}
+
+/// The headers and query parameter the auth added to a request.
+#[derive(Clone, Default)]
+pub struct AddedAuth {
+    pub headers: Vec<HeaderName>,
+    pub query: Option<String>,
+}
+
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Whether redirects are followed, and how many in a row.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Redirects {
+    pub follow: bool,
+    pub max_hops: usize,
+}
+
+impl Default for Redirects {
+    fn default() -> Self {
+        Self {
+            follow: true,
+            max_hops: DEFAULT_MAX_REDIRECTS,
+        }
+    }
+}
+
+impl Redirects {
+    pub fn is_default(&self) -> bool {
+        *self == Redirects::default()
+    }
+
+    pub fn label(&self) -> String {
+        if self.follow {
+            format!("follow up to {}", self.max_hops)
+        } else {
+            "don't follow".to_string()
+        }
+    }
+}
+
+/// The request as it appears in the editor, in a form that can be written to
+/// disk and loaded back into the Request/Reply tab.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SavedRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default, deserialize_with = "deserialize_headers")]
+    pub headers: Vec<KeyValue>,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub insecure: bool,
+    /// Query parameters switched off in the Params table. They aren't part of
+    /// the URL, so they're kept here until they're switched back on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_params: Vec<KeyValue>,
+    #[serde(default, skip_serializing_if = "BodyMode::is_raw")]
+    pub body_mode: BodyMode,
+    /// The fields of a form-urlencoded or multipart body. They're kept when
+    /// switching between the two, and while the body is raw.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub form: Vec<KeyValue>,
+    /// The path of the file sent in the file body mode.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body_file: String,
+    #[serde(default, skip_serializing_if = "Auth::is_none")]
+    pub auth: Auth,
+    /// Leaves the cookie jar out of this request, nothing is sent from it or
+    /// stored in it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_cookies: bool,
+    #[serde(default, skip_serializing_if = "Redirects::is_default")]
+    pub redirects: Redirects,
+}
+
+/// Header rows that hold credentials whatever they're called.
+static SECRET_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-amz-security-token",
+];
+
+/// Parts of header names that suggest a credential, like `X-Api-Key`.
+static SECRET_HEADER_WORDS: [&str; 5] = ["token", "secret", "password", "api-key", "apikey"];
+
+impl SavedRequest {
+    /// Empties the auth secrets and the credential header rows that aren't
+    /// `{{variable}}` references, before the request is written somewhere
+    /// that may be shared. Returns what was left out.
+    pub fn strip_secrets(&mut self) -> Vec<String> {
+        let mut stripped = Vec::new();
+        if self.auth.strip_secrets() {
+            stripped.push(format!("{} auth", self.auth.label()));
+        }
+        for header in &mut self.headers {
+            if !header.value.trim().is_empty()
+                && !header.value.contains("{{")
+                && is_secret_header(&header.name, &self.auth)
+            {
+                header.value.clear();
+                stripped.push(format!("{} header", header.name.trim()));
+            }
+        }
+        stripped
+    }
+}
+
+/// Whether a header row holds a credential: a well-known auth header, one
+/// whose name suggests a secret, or the header the API key auth goes in.
+fn is_secret_header(name: &str, auth: &Auth) -> bool {
+    let name = name.trim().to_lowercase();
+    let api_key_header = matches!(
+        auth,
+        Auth::ApiKey { name: key, location: ApiKeyLocation::Header, .. }
+            if key.trim().eq_ignore_ascii_case(&name)
+    );
+    api_key_header
+        || SECRET_HEADERS.contains(&name.as_str())
+        || SECRET_HEADER_WORDS.iter().any(|word| name.contains(word))
+}
+
+/// What came back from the server, kept around so the Response panel can show
+/// the body, the headers and the transfer details separately.
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub version: Version,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub elapsed: Duration,
+    /// Time until the response headers arrived, the rest of `elapsed` was
+    /// spent downloading the body.
+    pub wait: Duration,
+    pub url: String,
+    /// Every request sent and response received on the way to this one, the
+    /// last being this one. There's more than one when auth took another
+    /// round trip or redirects were followed.
+    pub exchanges: Vec<Exchange>,
+    /// The TLS handshake and certificates of an HTTPS response, or why they
+    /// couldn't be read. `None` for plain HTTP, and for HTTPS until they've
+    /// been read after the response is shown.
+    pub tls: Option<Result<TlsDetails, String>>,
+}
+
+/// One round trip, as the inspector shows it.
+#[derive(Clone)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    /// The headers the request went out with. Those added by the connection,
+    /// like `Host`, aren't known and left out.
+    pub request_headers: Vec<(String, String)>,
+    pub status: StatusCode,
+    pub headers: Vec<(String, String)>,
+    /// Time until the response headers arrived.
+    pub elapsed: Duration,
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
+            )
+        })
+        .collect()
+}
+
+impl HttpResponse {
+    pub fn status_line(&self) -> String {
+        format!("{:?} {}", self.version, self.status)
+    }
+
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    pub fn size(&self) -> usize {
+        self.body.len()
+    }
+
+    /// The redirects that were followed. The last exchange is this response,
+    /// a redirect there wasn't followed.
+    pub fn redirects_followed(&self) -> usize {
+        let earlier = &self.exchanges[..self.exchanges.len().saturating_sub(1)];
+        earlier
+            .iter()
+            .filter(|exchange| exchange.status.is_redirection())
+            .count()
+    }
+}
+
+/// An error with the errors that caused it, which is where reqwest keeps the
+/// details, e.g. why a certificate was refused.
+pub fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let text = cause.to_string();
+        if !message.contains(&text) {
+            message.push_str(": ");
+            message.push_str(&text);
+        }
+        source = cause.source();
+    }
+    message
+}
+
+/// A client with an environment's TLS and proxy settings. Redirects are
+/// followed by `send_request` itself, hop by hop, so the client doesn't
+/// follow them.
+pub fn client(tls: &TlsConfig, proxy: &ProxySettings) -> Result<Client> {
+    Ok(proxy
+        .apply(tls.apply(Client::builder()))?
+        .redirect(Policy::none())
+        .build()?)
+}
+
+/// Builds one leg of a request, with the jar's cookies for its URL.
+async fn build_request(
+    client: &Client,
+    method: Method,
+    url: &str,
+    headers: HeaderMap,
+    body: Option<RequestBody>,
+    cookies: Option<&CookieJar>,
+) -> Result<reqwest::Request, String> {
+    let mut builder = client.request(method, url).headers(headers);
+    match body {
+        Some(RequestBody::Text(body)) => builder = builder.body(body),
+        Some(RequestBody::File(path)) => {
+            let file = tokio::fs::File::open(&path)
+                .await
+                .map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+            // A streamed body would be sent chunked, the length lets servers
+            // that don't take chunked uploads accept it.
+            if let Ok(metadata) = file.metadata().await {
+                builder = builder.header(CONTENT_LENGTH, metadata.len());
+            }
+            builder = builder.body(file);
+        }
+        Some(RequestBody::Multipart(fields)) => {
+            builder = builder.multipart(multipart_form(fields).await?)
+        }
+        None => {}
+    }
+
+    let mut built = builder
+        .build()
+        .map_err(|e| format!("Error while making request: {}", e))?;
+    if let Some(jar) = cookies
+        && let Some(cookies) = jar.header(built.url().as_str())
+    {
+        // Cookies from the Headers block go first, the jar's are added on.
+        let value = match built.headers().get(COOKIE) {
+            Some(existing) => format!(
+                "{}; {}",
+                String::from_utf8_lossy(existing.as_bytes()),
+                cookies
+            ),
+            None => cookies,
+        };
+        let value = HeaderValue::from_str(&value)
+            .map_err(|e| format!("Invalid cookie from the jar: {}", e))?;
+        built.headers_mut().insert(COOKIE, value);
+    }
+
+    Ok(built)
+}
+
+/// Where a redirect response points, resolved against the URL it came from.
+fn redirect_target(url: &Url, status: StatusCode, headers: &HeaderMap) -> Option<Url> {
+    if !status.is_redirection() || status == StatusCode::NOT_MODIFIED {
+        return None;
+    }
+    let location = headers.get(LOCATION)?.to_str().ok()?;
+    url.join(location).ok()
+}
+
+/// Drops the query parameter `name`, the others are left as they are.
+fn remove_query_pair(url: &mut Url, name: &str) {
+    let Some(query) = url.query() else {
+        return;
+    };
+    let kept = query
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+            form_urlencoded::parse(key.as_bytes())
+                .next()
+                .is_none_or(|(key, _)| key != name)
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    url.set_query((!kept.is_empty()).then_some(kept.as_str()));
+}
+
+pub async fn send_request(client: Client, request: HttpRequest) -> Result<HttpResponse, String> {
+    let mut method = request.request_type.method();
+    let mut url =
+        Url::parse(&request.url).map_err(|e| format!("Error while making request: {}", e))?;
+    let mut headers = request.headers;
+    let mut body = request.body;
+    let mut exchanges = Vec::new();
+
+    let started_at = Instant::now();
+    loop {
+        let built = build_request(
+            &client,
+            method.clone(),
+            url.as_str(),
+            headers.clone(),
+            body.clone(),
+            request.cookies.as_ref(),
+        )
+        .await?;
+        let request_headers = header_pairs(built.headers());
+
+        let sent_at = Instant::now();
+        let output = client
+            .execute(built)
+            .await
+            .map_err(|e| format!("Error while making request: {}", error_chain(&e)))?;
+        let status = output.status();
+        if let Some(jar) = &request.cookies {
+            jar.store(
+                url.as_str(),
+                output
+                    .headers()
+                    .get_all(SET_COOKIE)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok()),
+            );
+        }
+        exchanges.push(Exchange {
+            method: method.to_string(),
+            url: url.to_string(),
+            request_headers,
+            status,
+            headers: header_pairs(output.headers()),
+            elapsed: sent_at.elapsed(),
+        });
+
+        let target = redirect_target(&url, status, output.headers());
+        if let Some(mut target) = target
+            && request.redirects.follow
+            && exchanges.len() <= request.redirects.max_hops
+        {
+            // A 303, or a 301/302 answering a POST, is followed with a GET
+            // and no body, as browsers and curl do. 307 and 308 keep both.
+            let to_get = match status {
+                StatusCode::SEE_OTHER => method != Method::HEAD,
+                StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => method == Method::POST,
+                _ => false,
+            };
+            if to_get {
+                method = Method::GET;
+                body = None;
+                headers.remove(CONTENT_TYPE);
+                headers.remove(CONTENT_LENGTH);
+            }
+            // Credentials aren't handed to another host.
+            if target.host_str() != url.host_str()
+                || target.port_or_known_default() != url.port_or_known_default()
+            {
+                headers.remove(AUTHORIZATION);
+                headers.remove(PROXY_AUTHORIZATION);
+                headers.remove(COOKIE);
+                for name in &request.added_auth.headers {
+                    headers.remove(name);
+                }
+                if let Some(name) = &request.added_auth.query {
+                    remove_query_pair(&mut target, name);
+                }
+            }
+            url = target;
+            continue;
+        }
+
+        let wait = started_at.elapsed();
+        let version = output.version();
+        let headers = header_pairs(output.headers());
+        let body = output
+            .bytes()
+            .await
+            .map_err(|e| format!("Error while reading response: {}", e))?;
+
+        return Ok(HttpResponse {
+            status,
+            version,
+            headers,
+            body: body.to_vec(),
+            elapsed: started_at.elapsed(),
+            wait,
+            url: url.to_string(),
+            exchanges,
+            tls: None,
+        });
+    }
+}
+
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// A client for tests, talking straight to their local servers whatever
+/// `HTTP_PROXY` says.
+#[cfg(test)]
+pub(crate) fn local_client(tls: &TlsConfig) -> Client {
+    let proxy = ProxySettings {
+        ignore_environment: true,
+        ..Default::default()
+    };
+    client(tls, &proxy).unwrap()
+}
+
+/// A plain GET for tests.
+#[cfg(test)]
+pub(crate) fn get(url: impl Into<String>) -> HttpRequest {
+    HttpRequest {
+        request_type: RequestType::GET,
+        url: url.into(),
+        headers: HeaderMap::new(),
+        body: None,
+        cookies: None,
+        redirects: Redirects::default(),
+        added_auth: AddedAuth::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        time::{sleep, timeout},
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn slow_request_runs_in_the_background_and_aborts_cleanly() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/slow", listener.local_addr().unwrap());
+        // Reads the request, then waits to see the client hang up rather
+        // than answering.
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            let read = socket.read(&mut buffer).await.unwrap();
+            assert!(buffer[..read].starts_with(b"GET /slow HTTP/1.1"));
+            let closed = timeout(Duration::from_secs(5), socket.read(&mut buffer)).await;
+            if closed.is_err() {
+                let _ = socket
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                    .await;
+            }
+            closed
+        });
+
+        let handle = tokio::spawn(send_request(local_client(&TlsConfig::default()), get(url)));
+        // The event loop polls the handle between frames.
+        for _ in 0..10 {
+            sleep(Duration::from_millis(20)).await;
+            assert!(!handle.is_finished());
+        }
+
+        handle.abort();
+        let error = handle.await.err().expect("the request was aborted");
+        assert!(error.is_cancelled());
+        let closed = server.await.unwrap();
+        assert!(
+            matches!(closed, Ok(Ok(0)) | Ok(Err(_))),
+            "the connection is dropped with the request"
+        );
+    }
+
+    #[tokio::test]
+    async fn request_finishes_once_the_server_answers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            let read = socket.read(&mut buffer).await.unwrap();
+            assert!(read > 0);
+            sleep(Duration::from_millis(200)).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+        });
+
+        let response = send_request(local_client(&TlsConfig::default()), get(url))
+            .await
+            .unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, b"ok");
+        assert!(response.wait >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn compressed_response_is_decoded() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+            assert!(request.contains("accept-encoding: gzip"), "{}", request);
+            // "hello, gzip"
+            let body = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xcb\x48\xcd\xc9\xc9\xd7\x51\x48\xaf\xca\x2c\x00\x00\x4a\x9b\xb1\x5c\x0b\x00\x00\x00";
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-encoding: gzip\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(body).await.unwrap();
+        });
+
+        let response = send_request(local_client(&TlsConfig::default()), get(url))
+            .await
+            .unwrap();
+        assert_eq!(response.body, b"hello, gzip");
+    }
+
+    #[test]
+    fn secrets_are_stripped_from_auth_and_header_rows() {
+        let mut request = SavedRequest {
+            headers: vec![
+                KeyValue::new("Authorization", "Bearer abc"),
+                KeyValue::new("Cookie", "sid=1"),
+                KeyValue::new("X-Api-Key", "{{key}}"),
+                KeyValue::new("X-Session-Token", "t0k"),
+                KeyValue::new("X-Tenant", "acme"),
+                KeyValue::new("X-Custom", "k3y"),
+            ],
+            auth: Auth::ApiKey {
+                name: "x-custom".to_string(),
+                value: "k3y".to_string(),
+                location: ApiKeyLocation::Header,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            request.strip_secrets(),
+            [
+                "API key auth",
+                "Authorization header",
+                "Cookie header",
+                "X-Session-Token header",
+                "X-Custom header",
+            ]
+        );
+        let values: Vec<&str> = request.headers.iter().map(|h| h.value.as_str()).collect();
+        assert_eq!(values, ["", "", "{{key}}", "", "acme", ""]);
+        assert!(request.strip_secrets().is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_followed_redirects_are_counted() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0; 4096];
+                let _ = socket.read(&mut buffer).await.unwrap();
+                socket
+                    .write_all(
+                        b"HTTP/1.1 302 Found\r\nlocation: /next\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    )
+                    .await
+                    .unwrap();
+            }
+        });
+        let send = |redirects: Redirects| {
+            let request = HttpRequest {
+                redirects,
+                ..get(url.clone())
+            };
+            send_request(local_client(&TlsConfig::default()), request)
+        };
+
+        let response = send(Redirects {
+            follow: true,
+            max_hops: 2,
+        })
+        .await
+        .unwrap();
+        assert_eq!(response.status, StatusCode::FOUND);
+        assert_eq!(response.exchanges.len(), 3);
+        assert_eq!(response.redirects_followed(), 2);
+
+        let response = send(Redirects {
+            follow: false,
+            max_hops: 2,
+        })
+        .await
+        .unwrap();
+        assert_eq!(response.redirects_followed(), 0);
+    }
+}
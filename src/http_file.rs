@@ -0,0 +1,414 @@
+use crate::{
+    environment::substitute,
+    http::{BodyMode, KeyValue, RequestType, SavedRequest},
+};
+use color_eyre::{Result, eyre::eyre};
+use std::{collections::BTreeMap, fs, ops::Range, path::Path};
+
+/// Extensions used by the VS Code REST Client and the JetBrains HTTP client.
+pub static HTTP_FILE_EXTENSIONS: [&str; 2] = ["http", "rest"];
+
+/// A request found in a `.http` file, along with where each part of it lives
+/// in the file so edits can be written back without touching the rest.
+pub struct HttpFileRequest {
+    pub name: String,
+    pub request: SavedRequest,
+    /// The request line and any `?`/`&` query continuation lines after it.
+    request_line: Range<usize>,
+    /// Trailing `HTTP/1.1` on the request line, kept when the URL changes.
+    version: Option<String>,
+    /// Whether the request line spelled out the method, `GET` is implied if not.
+    explicit_method: bool,
+    headers: Range<usize>,
+    /// Where the body starts, `None` if there's no blank line after the
+    /// headers. Trailing blank lines and response handlers aren't part of the
+    /// body.
+    body: Option<Range<usize>>,
+    /// Why the request can't be loaded as written, e.g. a method that can't
+    /// be sent.
+    pub unsupported: Option<String>,
+}
+
+pub struct HttpFile {
+    /// `@name = value` definitions in file order, later ones can refer to
+    /// earlier ones.
+    pub variables: Vec<(String, String)>,
+    pub requests: Vec<HttpFileRequest>,
+}
+
+pub fn is_http_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| HTTP_FILE_EXTENSIONS.iter().any(|e| ext == *e))
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with("//")
+}
+
+fn is_separator(line: &str) -> bool {
+    line.trim_start().starts_with("###")
+}
+
+fn is_method(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// A JetBrains response handler, `> {% script %}` or `> ./script.js`, a
+/// `>> file` response redirect or a `<> ./previous` response reference. They
+/// come after the body.
+fn is_response_handler(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('>') || line.starts_with("<>")
+}
+
+/// A JetBrains `< ./file` line, which sends the file's contents as the body.
+fn is_file_include(line: &str) -> bool {
+    line.trim_start().starts_with("< ")
+}
+
+/// `@name = value`, as long as it isn't an email address or a decorator.
+fn parse_variable(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.trim().strip_prefix('@')?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some((name.to_string(), value.trim().to_string()))
+}
+
+/// The value of a `# @name foo` or `// @name foo` comment.
+fn request_name_comment(line: &str) -> Option<String> {
+    let line = line.trim_start();
+    let comment = line
+        .strip_prefix("//")
+        .or_else(|| line.strip_prefix('#'))?
+        .trim_start();
+    let name = comment.strip_prefix("@name")?.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn lines(contents: &str) -> Vec<&str> {
+    contents
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect()
+}
+
+pub fn parse(contents: &str) -> HttpFile {
+    let lines = lines(contents);
+    let mut file = HttpFile {
+        variables: Vec::new(),
+        requests: Vec::new(),
+    };
+
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + 1..lines.len())
+            .find(|&i| is_separator(lines[i]))
+            .unwrap_or(lines.len());
+        parse_block(&lines, start..end, &mut file);
+        start = end;
+    }
+
+    file
+}
+
+fn parse_block(lines: &[&str], block: Range<usize>, file: &mut HttpFile) {
+    let mut name = None;
+    let mut index = block.start;
+    if is_separator(lines[index]) {
+        let title = lines[index].trim_start().trim_start_matches('#').trim();
+        if !title.is_empty() {
+            name = Some(title.to_string());
+        }
+        index += 1;
+    }
+
+    // Everything before the request line is comments, blank lines and
+    // variable definitions.
+    while index < block.end {
+        let line = lines[index];
+        if let Some(request_name) = request_name_comment(line) {
+            name = Some(request_name);
+        } else if let Some(variable) = parse_variable(line) {
+            file.variables.push(variable);
+        } else if !line.trim().is_empty() && !is_comment(line) {
+            break;
+        }
+        index += 1;
+    }
+    if index == block.end {
+        return;
+    }
+
+    let request_line_start = index;
+    let mut text = lines[index].trim().to_string();
+    index += 1;
+    while index < block.end {
+        let line = lines[index].trim();
+        if !line.starts_with('?') && !line.starts_with('&') {
+            break;
+        }
+        text.push_str(line);
+        index += 1;
+    }
+
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    let explicit_method = words.len() > 1 && is_method(words[0]);
+    let method = if explicit_method {
+        words.remove(0).to_string()
+    } else {
+        "GET".to_string()
+    };
+    let version = match words.last() {
+        Some(word) if word.starts_with("HTTP/") => words.pop().map(str::to_string),
+        _ => None,
+    };
+    let url = words.join(" ");
+    let request_line = request_line_start..index;
+
+    let headers_start = index;
+    let mut headers = Vec::new();
+    while index < block.end && !lines[index].trim().is_empty() && !is_response_handler(lines[index])
+    {
+        if !is_comment(lines[index])
+            && let Some((header, value)) = lines[index].split_once(':')
+        {
+            headers.push(KeyValue::new(header.trim(), value.trim()));
+        }
+        index += 1;
+    }
+    let headers_range = headers_start..index;
+
+    let body = if index < block.end && !is_response_handler(lines[index]) {
+        let body_start = index + 1;
+        let mut body_end = (body_start..block.end)
+            .find(|&i| is_response_handler(lines[i]))
+            .unwrap_or(block.end);
+        while body_end > body_start && lines[body_end - 1].trim().is_empty() {
+            body_end -= 1;
+        }
+        Some(body_start..body_end)
+    } else {
+        None
+    };
+    let body_text = body
+        .as_ref()
+        .map(|range| lines[range.clone()].join("\n"))
+        .unwrap_or_default();
+
+    let include = body
+        .clone()
+        .into_iter()
+        .flatten()
+        .find(|&i| is_file_include(lines[i]));
+    let unsupported = if method.parse::<RequestType>().is_err() {
+        Some(format!("unsupported method {}", method))
+    } else {
+        include.map(|i| {
+            format!(
+                "bodies read from a file ({}) aren't supported",
+                lines[i].trim()
+            )
+        })
+    };
+
+    file.requests.push(HttpFileRequest {
+        name: name.unwrap_or_else(|| format!("{} {}", method, url)),
+        request: SavedRequest {
+            method,
+            url,
+            headers,
+            body: body_text,
+            ..Default::default()
+        },
+        request_line,
+        version,
+        explicit_method,
+        headers: headers_range,
+        body,
+        unsupported,
+    });
+}
+
+pub fn load(path: &Path) -> Result<HttpFile> {
+    Ok(parse(&fs::read_to_string(path)?))
+}
+
+/// Writes `request` back over request number `index` in the file. Only the
+/// parts that changed are rewritten, comments, variables, spacing and the
+/// other requests are left exactly as they were.
+pub fn save_request(path: &Path, index: usize, request: &SavedRequest) -> Result<()> {
+    if request.body_mode != BodyMode::Raw {
+        return Err(eyre!(
+            ".http files only hold raw bodies, switch the body mode to raw first"
+        ));
+    }
+    if !request.auth.is_none() {
+        return Err(eyre!(
+            ".http files have no auth section, use an Authorization header instead"
+        ));
+    }
+    let contents = fs::read_to_string(path)?;
+    let line_ending = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let file = parse(&contents);
+    let original = file
+        .requests
+        .get(index)
+        .ok_or_else(|| eyre!("{} no longer has request {}", path.display(), index + 1))?;
+    // The format has no way to switch a header off, disabled rows are left out.
+    let new_headers: Vec<&KeyValue> = request
+        .headers
+        .iter()
+        .filter(|header| header.enabled && !header.name.trim().is_empty())
+        .collect();
+
+    let mut lines: Vec<String> = lines(&contents).into_iter().map(str::to_string).collect();
+
+    // Rewrite from the bottom up so the earlier ranges stay valid.
+    if request.body != original.request.body {
+        let body_lines: Vec<String> = if request.body.is_empty() {
+            Vec::new()
+        } else {
+            request.body.lines().map(str::to_string).collect()
+        };
+        match &original.body {
+            Some(range) => {
+                lines.splice(range.clone(), body_lines);
+            }
+            None if !body_lines.is_empty() => {
+                let at = original.headers.end;
+                lines.splice(at..at, std::iter::once(String::new()).chain(body_lines));
+            }
+            None => {}
+        }
+    }
+
+    let original_headers: Vec<&KeyValue> = original.request.headers.iter().collect();
+    if new_headers != original_headers {
+        // Header lines are replaced one for one so comments between them stay
+        // where they were.
+        let mut remaining = new_headers
+            .iter()
+            .map(|header| format!("{}: {}", header.name.trim(), header.value));
+        let mut header_lines = Vec::new();
+        for line in &lines[original.headers.clone()] {
+            if is_comment(line) {
+                header_lines.push(line.clone());
+            } else if let Some(header_line) = remaining.next() {
+                header_lines.push(header_line);
+            }
+        }
+        header_lines.extend(remaining);
+        lines.splice(original.headers.clone(), header_lines);
+    }
+
+    if request.method != original.request.method || request.url != original.request.url {
+        let mut request_line = if request.method == "GET" && !original.explicit_method {
+            request.url.clone()
+        } else {
+            format!("{} {}", request.method, request.url)
+        };
+        if let Some(version) = &original.version {
+            request_line.push(' ');
+            request_line.push_str(version);
+        }
+        lines.splice(original.request_line.clone(), std::iter::once(request_line));
+    }
+
+    fs::write(path, lines.join(line_ending))?;
+
+    Ok(())
+}
+
+/// Adds the file's variables on top of `variables`, resolving references to
+/// the environment and to variables defined earlier in the file.
+pub fn resolve_variables(
+    file_variables: &[(String, String)],
+    variables: &mut BTreeMap<String, String>,
+) {
+    for (name, value) in file_variables {
+        let value = substitute(value, variables);
+        variables.insert(name.clone(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    static WITH_HANDLERS: &str = r#"POST https://example.com/login
+Content-Type: application/json
+
+{"user": "me"}
+
+> {%
+  client.global.set("token", response.body.token);
+%}
+
+###
+GET https://example.com/me
+> ./check.js
+"#;
+
+    #[test]
+    fn methods_that_cant_be_sent_are_flagged() {
+        let file = parse("PROPFIND https://example.com/dav\n\n###\nDELETE https://example.com/1\n");
+        assert_eq!(
+            file.requests[0].unsupported.as_deref(),
+            Some("unsupported method PROPFIND")
+        );
+        assert!(file.requests[1].unsupported.is_none());
+        assert_eq!(file.requests[1].request.method, "DELETE");
+    }
+
+    #[test]
+    fn response_handlers_are_not_part_of_the_request() {
+        let file = parse(WITH_HANDLERS);
+        assert_eq!(file.requests[0].request.body, r#"{"user": "me"}"#);
+        assert!(file.requests[0].unsupported.is_none());
+        assert!(file.requests[1].request.headers.is_empty());
+        assert!(file.requests[1].request.body.is_empty());
+    }
+
+    #[test]
+    fn bodies_from_files_are_flagged() {
+        let file = parse("POST https://example.com/upload\n\n< ./payload.json\n");
+        assert_eq!(
+            file.requests[0].unsupported.as_deref(),
+            Some("bodies read from a file (< ./payload.json) aren't supported")
+        );
+        // XML isn't mistaken for an include.
+        let file = parse("POST https://example.com/soap\n\n<Envelope/>\n");
+        assert!(file.requests[0].unsupported.is_none());
+    }
+
+    #[test]
+    fn saving_a_body_keeps_the_response_handler() {
+        let path = env::temp_dir().join(format!("pingpong-test-{}.http", std::process::id()));
+        fs::write(&path, WITH_HANDLERS).unwrap();
+
+        let mut request = parse(WITH_HANDLERS).requests.remove(0).request;
+        request.body = r#"{"user": "you"}"#.to_string();
+        save_request(&path, 0, &request).unwrap();
+        let mut request = parse(WITH_HANDLERS).requests.remove(1).request;
+        request.body = "ping".to_string();
+        save_request(&path, 1, &request).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            saved,
+            WITH_HANDLERS
+                .replace(r#""me""#, r#""you""#)
+                .replace("/me\n", "/me\n\nping\n")
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    environment::{self, Environment},
+    har, openapi, postman,
+};
+use color_eyre::{Result, eyre::eyre};
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// What an import created, shown in the status bar afterwards.
+pub struct ImportSummary {
+    pub collection: String,
+    pub requests: usize,
+    pub skipped: Vec<String>,
+    /// Requests saved without some of their secrets, with what was left out.
+    pub stripped: Vec<String>,
+    pub environment: Option<String>,
+}
+
+impl ImportSummary {
+    pub fn record_stripped(&mut self, name: &str, stripped: Vec<String>) {
+        if !stripped.is_empty() {
+            self.stripped
+                .push(format!("{} ({})", name, stripped.join(", ")));
+        }
+    }
+}
+
+/// Imports a Postman collection, an OpenAPI spec or a HAR archive as a new
+/// collection. The format is detected from the document itself, YAML is
+/// accepted as well as JSON.
+pub fn import_file(file: &Path) -> Result<ImportSummary> {
+    let contents =
+        fs::read_to_string(file).map_err(|e| eyre!("Could not read {}: {}", file.display(), e))?;
+    let document: Value = match serde_json::from_str(&contents) {
+        Ok(document) => document,
+        Err(_) => serde_yaml::from_str(&contents)
+            .map_err(|e| eyre!("{} is neither JSON nor YAML: {}", file.display(), e))?,
+    };
+
+    if document.get("openapi").is_some() {
+        openapi::import_spec(document)
+    } else if document.get("info").is_some() && document.get("item").is_some() {
+        postman::import_collection(document)
+    } else if document.pointer("/log/entries").is_some() {
+        let name = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        har::import_har(document, &name)
+    } else if document.get("swagger").is_some() {
+        Err(eyre!(
+            "Swagger 2.0 specs aren't supported, convert to OpenAPI 3 first"
+        ))
+    } else {
+        Err(eyre!("Unrecognized file format"))
+    }
+}
+
+/// Saves imported variables as an environment called `name`, keeping any
+/// values already set in an existing environment of that name.
+pub fn save_variables(name: &str, variables: BTreeMap<String, String>) -> Result<String> {
+    let name = name.replace('/', "_");
+    let mut environment = environment::load_environments()
+        .into_iter()
+        .find(|e| e.name == name)
+        .unwrap_or_else(|| Environment {
+            name: name.clone(),
+            ..Default::default()
+        });
+    for (key, value) in variables {
+        environment.variables.entry(key).or_insert(value);
+    }
+    environment::save_environment(&environment)?;
+
+    Ok(name)
+}
@@ -1,16 +1,56 @@
-use color_eyre::Result;
-use crossterm::event::{self, KeyCode};
-use ratatui::{DefaultTerminal, prelude::*, widgets::*};
-use reqwest::{
-    blocking::Client,
-    header::{HeaderMap, HeaderName, HeaderValue},
+mod auth;
+mod auth_editor;
+mod collections;
+mod cookies;
+mod curl;
+mod digest;
+mod environment;
+mod har;
+mod highlight;
+mod history;
+mod http;
+mod http_file;
+mod import;
+mod oauth;
+mod openapi;
+mod postman;
+mod proxy;
+mod sigv4;
+mod storage;
+mod table_editor;
+mod tls;
+mod tls_info;
+mod viewer;
+
+use auth::Auth;
+use auth_editor::AuthEditor;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use collections::{CollectionNode, NodeKind};
+use color_eyre::{Result, eyre::eyre};
+use cookies::{CookieJar, StoredCookie};
+use crossterm::{
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, KeyCode},
+    execute,
+};
+use environment::{Environment, resolve_request};
+use highlight::{ContentKind, format_body, raw_lines};
+use history::{HistoryEntry, ResponseSummary, SentRequest, append_history, load_history};
+use http::{
+    AddedAuth, BodyMode, HttpRequest, HttpResponse, KeyValue, Redirects, RequestType, SavedRequest,
+    build_body, build_headers, content_type_for, enabled_fields, form_body, format_size,
+    header_value, headers_from_json, parse_into_https, query_params, with_query_params,
 };
+use oauth::TokenCache;
+use proxy::ProxySettings;
+use ratatui::{DefaultTerminal, prelude::*, widgets::*};
+use reqwest::{Client, header::HeaderMap};
 use std::{
+    borrow::Cow,
     cmp,
-    collections::HashMap,
-    fmt::{self},
-    io,
-    str::FromStr,
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::PathBuf,
     sync::{
         Arc, Mutex,
         atomic::{AtomicUsize, Ordering},
@@ -19,8 +59,12 @@ use std::{
     time::Duration,
 };
 use strum::IntoEnumIterator;
-use strum_macros::{Display, EnumIter, EnumString, FromRepr};
-use tokio::{runtime::Runtime, time::Instant};
+use strum_macros::{Display, EnumIter, FromRepr};
+use table_editor::{TableEditor, TableKind, path_completions};
+use tls::{TlsConfig, TlsSettings};
+use tls_info::TlsDetails;
+use tokio::{runtime::Runtime, task::JoinHandle, time::Instant};
+use viewer::ResponseViewer;
 
 struct Dropdown {
     items: Vec<String>,
@@ -50,7 +94,7 @@ impl Dropdown {
     fn previous(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
-                if i == 0 && self.items.len() > 0 {
+                if i == 0 && !self.items.is_empty() {
                     self.items.len() - 1
                 } else {
                     i - 1
@@ -69,6 +113,12 @@ impl Dropdown {
         self.items[0].clone()
     }
 
+    fn select_value(&mut self, value: &str) {
+        if let Some(i) = self.items.iter().position(|item| item == value) {
+            self.state.select(Some(i));
+        }
+    }
+
     fn toggle(&mut self) {
         self.open = !self.open
     }
@@ -83,7 +133,7 @@ struct DisplayString {
 impl DisplayString {
     fn new(value: String) -> Self {
         Self {
-            value: value,
+            value,
             edit_mode: false,
         }
     }
@@ -92,6 +142,10 @@ impl DisplayString {
         self.value.push(ch);
     }
 
+    fn add_str(&mut self, value: &str) {
+        self.value.push_str(value);
+    }
+
     fn remove_last_char(&mut self) {
         self.value.pop();
     }
@@ -109,28 +163,6 @@ impl DisplayString {
     }
 }
 
-#[derive(EnumIter, EnumString)]
-enum RequestType {
-    GET,
-    POST,
-    PUT,
-    PATCH,
-    DELETE,
-}
-
-impl fmt::Display for RequestType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let request_type = match self {
-            RequestType::GET => "GET",
-            RequestType::POST => "POST",
-            RequestType::PUT => "PUT",
-            RequestType::PATCH => "PATCH",
-            RequestType::DELETE => "DELETE",
-        };
-        write!(f, "{}", request_type)
-    }
-}
-
 #[derive(Default, Clone, Copy, Display, FromRepr, EnumIter, PartialEq)]
 enum SelectedTab {
     #[default]
@@ -138,12 +170,27 @@ enum SelectedTab {
     RequestReply,
     #[strum(to_string = "Load Test")]
     LoadTest,
+    #[strum(to_string = "History")]
+    History,
+    #[strum(to_string = "Collections")]
+    Collections,
+    #[strum(to_string = "Environments")]
+    Environments,
+    #[strum(to_string = "Cookies")]
+    Cookies,
 }
 
 impl SelectedTab {
     fn all() -> &'static [SelectedTab] {
         use SelectedTab::*;
-        &[RequestReply, LoadTest]
+        &[
+            RequestReply,
+            LoadTest,
+            History,
+            Collections,
+            Environments,
+            Cookies,
+        ]
     }
 
     fn previous(self) -> Self {
@@ -162,30 +209,68 @@ impl SelectedTab {
         match self {
             SelectedTab::RequestReply => "Send individual requests to your endpoint".to_string(),
             SelectedTab::LoadTest => "Load test your API".to_string(),
+            SelectedTab::History => "Browse and reload previously sent requests".to_string(),
+            SelectedTab::Collections => "Saved requests, organized in folders".to_string(),
+            SelectedTab::Environments => "Variables substituted into {{placeholders}}".to_string(),
+            SelectedTab::Cookies => "Cookies kept for the active environment".to_string(),
         }
     }
 }
 
-fn parse_into_https(url: &str) -> String {
-    if url.starts_with("http") || url.starts_with("https") {
-        return url.to_string();
+#[derive(Default, Clone, Copy, Display, FromRepr, EnumIter, PartialEq)]
+enum ResponseView {
+    #[default]
+    Body,
+    Headers,
+    /// The requests and responses that led to the response, with the headers
+    /// each request went out with.
+    Exchange,
+    /// What the TLS handshake negotiated and the server's certificates.
+    #[strum(to_string = "TLS")]
+    Tls,
+    Info,
+}
+
+impl ResponseView {
+    fn all() -> &'static [ResponseView] {
+        use ResponseView::*;
+        &[Body, Headers, Exchange, Tls, Info]
     }
 
-    if url.contains("localhost") {
-        return format!("http://{}", url);
+    fn next(self) -> Self {
+        let next_index = (self as usize + 1) % Self::all().len();
+        Self::from_repr(next_index).unwrap_or(self)
     }
+}
 
-    format!("https://{}", url)
+/// The list of the Environments tab that keys go to.
+#[derive(Clone, Copy, PartialEq)]
+enum EnvironmentPane {
+    Environments,
+    Variables,
+    Tls,
+    Proxy,
 }
 
-fn build_headers(headers: &str) -> Result<HeaderMap> {
-    let map: HashMap<String, String> = serde_json::from_str(headers)?;
-    let mut out = HeaderMap::new();
-    for (key, value) in map {
-        out.insert(HeaderName::from_str(&key)?, HeaderValue::from_str(&value)?);
+impl EnvironmentPane {
+    fn next(self) -> Self {
+        match self {
+            EnvironmentPane::Environments => EnvironmentPane::Variables,
+            EnvironmentPane::Variables => EnvironmentPane::Tls,
+            EnvironmentPane::Tls => EnvironmentPane::Proxy,
+            EnvironmentPane::Proxy => EnvironmentPane::Environments,
+        }
     }
+}
 
-    Ok(out)
+fn status_color(status: reqwest::StatusCode) -> Color {
+    match status.as_u16() {
+        100..=199 => Color::Cyan,
+        200..=299 => Color::Green,
+        300..=399 => Color::Yellow,
+        400..=499 => Color::LightRed,
+        _ => Color::Red,
+    }
 }
 
 static PLACEHOLDER_URL_VALUE: &str = "<Enter URL here>";
@@ -195,6 +280,7 @@ static PLACEHOLDER_HEADERS: &str = r#"{"content-type": "application/json"}"#;
 fn main() -> Result<()> {
     color_eyre::install()?;
     let terminal = ratatui::init();
+    execute!(io::stdout(), EnableBracketedPaste)?;
     let request_types = RequestType::iter().map(|r| r.to_string()).collect();
 
     let app = App::new(
@@ -205,6 +291,7 @@ fn main() -> Result<()> {
         "",
     );
     let result = app.run(terminal);
+    execute!(io::stdout(), DisableBracketedPaste)?;
     ratatui::restore();
     result
 }
@@ -216,14 +303,125 @@ struct App {
     request_type: Dropdown,
     request_url: DisplayString,
     request_body: DisplayString,
-    headers: DisplayString,
+    body_mode: BodyMode,
+    /// The fields of a form-urlencoded or multipart body.
+    form: TableEditor,
+    /// The path of the file sent in the file body mode.
+    body_file: DisplayString,
+    params: TableEditor,
+    /// The URL the Params table was last synced with.
+    params_url: String,
+    headers: TableEditor,
+    auth: AuthEditor,
+    insecure: bool,
+    /// Leaves the cookie jar out of the request in the editor.
+    no_cookies: bool,
+    redirects: Redirects,
     response: DisplayString,
+    last_response: Option<HttpResponse>,
+    response_view: ResponseView,
+    response_body_lines: Vec<Line<'static>>,
+    response_raw: bool,
+    viewer: ResponseViewer,
     selected_tab: SelectedTab,
     load_test_url: DisplayString,
     load_test_result: Arc<Mutex<DisplayString>>,
+    history: Vec<HistoryEntry>,
+    history_filter: DisplayString,
+    history_state: ListState,
+    collection_nodes: Vec<CollectionNode>,
+    collections_expanded: HashSet<PathBuf>,
+    /// `.http` files opened from outside the collections directory.
+    http_files: Vec<PathBuf>,
+    /// The `.http` file request in the editor, if that's where it came from.
+    opened_http_request: Option<(PathBuf, usize)>,
+    http_file_variables: Vec<(String, String)>,
+    collections_state: ListState,
+    opened_request: Option<PathBuf>,
+    environments: Vec<Environment>,
+    active_environment: Option<String>,
+    environments_state: ListState,
+    variables_state: ListState,
+    environment_pane: EnvironmentPane,
+    tls_state: ListState,
+    /// The proxy settings used when the active environment has none.
+    proxy: ProxySettings,
+    /// Whether the Proxy block shows the global settings rather than the
+    /// selected environment's.
+    proxy_global: bool,
+    proxy_state: ListState,
+    /// The jar of the active environment.
+    cookies: CookieJar,
+    cookies_state: ListState,
+    show_preview: bool,
+    prompt: Option<Prompt>,
+    status_message: Option<String>,
     client: Client,
+    /// OAuth2 tokens, kept until they expire.
+    tokens: TokenCache,
+    runtime: Runtime,
+    in_flight_request: Option<InFlightRequest>,
+    /// Reads the TLS details of the last response once it's shown.
+    tls_inspection: Option<JoinHandle<Option<Result<TlsDetails, String>>>>,
+}
+
+/// A single request running on the app's runtime while the UI keeps drawing.
+struct InFlightRequest {
+    /// The request as edited, `{{placeholders}}` and all. That's what goes
+    /// into the history, the resolved copy may hold an environment's
+    /// secrets.
+    request: SavedRequest,
+    /// What was sent, with the credentials masked, for the history.
+    sent: SentRequest,
+    /// The settings the TLS details are read with, once the response is in.
+    tls: TlsConfig,
+    proxy: ProxySettings,
+    started_at: Instant,
+    handle: JoinHandle<Result<HttpResponse, String>>,
+}
+
+/// A single line text input shown on top of the current tab.
+struct Prompt {
+    title: String,
+    input: DisplayString,
+    action: PromptAction,
+}
+
+enum PromptAction {
+    SaveRequest,
+    NewFolder(PathBuf),
+    Rename(PathBuf, NodeKind),
+    ConfirmDelete(PathBuf, NodeKind),
+    NewEnvironment,
+    ConfirmDeleteEnvironment(String),
+    SetVariable(String, Option<String>),
+    SetCookie(Option<StoredCookie>),
+    SetRedirects,
+    SetTls(String, usize),
+    /// A proxy field, of an environment or of the global settings when
+    /// there's no name.
+    SetProxy(Option<String>, usize),
+    ConfirmDeleteCookies(String),
+    ImportCurl,
+    ImportFile,
+    ExportHar,
+    OpenHttpFile,
+}
+
+impl Prompt {
+    fn new(title: &str, value: String, action: PromptAction) -> Self {
+        let mut input = DisplayString::new(value);
+        input.toggle_mode();
+        Self {
+            title: title.to_string(),
+            input,
+            action,
+        }
+    }
 }
 
+static SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 impl App {
     fn new(
         request_types: Vec<String>,
@@ -232,351 +430,1791 @@ impl App {
         default_headers: &str,
         default_response: &str,
     ) -> Self {
+        let (cookies, mut status_message) = match CookieJar::load(None) {
+            Ok(cookies) => (cookies, None),
+            Err(e) => (CookieJar::default(), Some(e.to_string())),
+        };
+        let proxy = proxy::load_global().unwrap_or_else(|e| {
+            status_message = Some(e.to_string());
+            ProxySettings::default()
+        });
         Self {
             should_exit: false,
             active_block: 0,
-            block_size: 4,
+            block_size: 7,
             request_type: Dropdown::new(request_types),
             request_url: DisplayString::new(default_request_url.to_string()),
             request_body: DisplayString::new(default_request_body.to_string()),
-            headers: DisplayString::new(default_headers.to_string()),
+            body_mode: BodyMode::Raw,
+            form: TableEditor::new(TableKind::FormUrlencoded, Vec::new()),
+            body_file: DisplayString::new(String::new()),
+            params: TableEditor::new(TableKind::Params, Vec::new()),
+            params_url: String::new(),
+            headers: TableEditor::new(
+                TableKind::Headers,
+                headers_from_json(default_headers).unwrap_or_default(),
+            ),
+            auth: AuthEditor::new(),
+            insecure: false,
+            no_cookies: false,
+            redirects: Redirects::default(),
             response: DisplayString::new(default_response.to_string()),
+            last_response: None,
+            response_view: ResponseView::Body,
+            response_body_lines: Vec::new(),
+            response_raw: false,
+            viewer: ResponseViewer::new(),
             selected_tab: SelectedTab::RequestReply,
             load_test_url: DisplayString::new("".to_string()),
             load_test_result: Arc::new(Mutex::new(DisplayString::new("".to_string()))),
-            client: Client::new(),
+            history: load_history(),
+            history_filter: DisplayString::new("".to_string()),
+            history_state: ListState::default().with_selected(Some(0)),
+            collection_nodes: collections::load_tree(&HashSet::new(), &[]),
+            collections_expanded: HashSet::new(),
+            http_files: Vec::new(),
+            opened_http_request: None,
+            http_file_variables: Vec::new(),
+            collections_state: ListState::default().with_selected(Some(0)),
+            opened_request: None,
+            environments: environment::load_environments(),
+            active_environment: None,
+            environments_state: ListState::default().with_selected(Some(0)),
+            variables_state: ListState::default().with_selected(Some(0)),
+            environment_pane: EnvironmentPane::Environments,
+            tls_state: ListState::default().with_selected(Some(0)),
+            proxy,
+            proxy_global: false,
+            proxy_state: ListState::default().with_selected(Some(0)),
+            cookies,
+            cookies_state: ListState::default().with_selected(Some(0)),
+            show_preview: false,
+            prompt: None,
+            status_message,
+            client: http::client(&TlsConfig::default(), &ProxySettings::default())
+                .unwrap_or_default(),
+            tokens: TokenCache::default(),
+            runtime: Runtime::new().unwrap(),
+            in_flight_request: None,
+            tls_inspection: None,
         }
     }
 
     fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
         while !self.should_exit {
+            self.poll_in_flight_request();
+            self.poll_tls_inspection();
             terminal.draw(|frame| self.draw(frame))?;
             self.handle_events()?;
+            self.sync_params_from_url();
         }
+        self.cancel_request();
         Ok(())
     }
 
-    fn handle_events(&mut self) -> io::Result<()> {
-        if event::poll(Duration::from_millis(100))? {
-            let any_block_in_edit_mode = self.request_body.edit_mode.clone()
-                || self.request_url.edit_mode.clone()
-                || self.headers.edit_mode.clone()
-                || self.load_test_url.edit_mode.clone();
-
-            let mut display_strings = vec![
-                &mut self.request_url,
-                &mut self.request_body,
-                &mut self.headers,
-            ];
-            if let event::Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char(c) => {
-                        if c == 'h' && !any_block_in_edit_mode {
-                            self.selected_tab = self.selected_tab.previous();
-                        }
+    fn send_request(&mut self) {
+        if self.in_flight_request.is_some() {
+            return;
+        }
 
-                        if c == 'l' && !any_block_in_edit_mode {
-                            self.selected_tab = self.selected_tab.next();
-                        }
+        let unresolved = self.current_request();
+        let saved_request = resolve_request(&unresolved, &self.active_variables());
+        let mut parsed_headers = match build_headers(&saved_request.headers) {
+            Ok(headers) => headers,
+            Err(e) => {
+                self.response
+                    .update_value(format!("Invalid headers: {}", e));
+                return;
+            }
+        };
+        let request_type = saved_request.method.parse::<RequestType>().unwrap();
+        let body = match build_body(&saved_request, &mut parsed_headers) {
+            Ok(body) => body,
+            Err(e) => {
+                self.response.update_value(format!("Invalid body: {}", e));
+                return;
+            }
+        };
+        let mut url = parse_into_https(&saved_request.url);
+        if let Err(e) = saved_request.auth.apply(&mut url, &mut parsed_headers) {
+            self.response.update_value(format!("Invalid auth: {}", e));
+            return;
+        }
+        let sent = SentRequest::new(&url, &parsed_headers, &saved_request.auth);
+        let request = HttpRequest {
+            request_type,
+            url,
+            headers: parsed_headers,
+            body,
+            cookies: (!saved_request.no_cookies && self.cookies.is_enabled())
+                .then(|| self.cookies.clone()),
+            redirects: saved_request.redirects,
+            added_auth: AddedAuth::default(),
+        };
 
-                        if c == 'q' && !any_block_in_edit_mode {
-                            self.should_exit = true;
-                        }
+        let settings = self.active_tls();
+        let tls = match settings.load() {
+            Ok(mut tls) => {
+                tls.insecure |= saved_request.insecure;
+                tls
+            }
+            Err(e) => {
+                self.response
+                    .update_value(format!("Invalid TLS settings: {}", e));
+                return;
+            }
+        };
+        let proxy = self.active_proxy();
+        let client = if saved_request.insecure || !settings.is_default() || !proxy.is_default() {
+            match http::client(&tls, &proxy) {
+                Ok(client) => client,
+                Err(e) => {
+                    self.response
+                        .update_value(format!("Invalid TLS or proxy settings: {}", e));
+                    return;
+                }
+            }
+        } else {
+            self.client.clone()
+        };
+        let auth = saved_request.auth.clone();
+        let tokens = self.tokens.clone();
+        let handle = self.runtime.spawn(async move {
+            let mut response = auth.send(client, &tokens, request).await?;
+            auth.mask_response(&mut response);
+            Ok(response)
+        });
+        self.in_flight_request = Some(InFlightRequest {
+            request: unresolved,
+            sent,
+            tls,
+            proxy,
+            started_at: Instant::now(),
+            handle,
+        });
+    }
 
-                        if self.selected_tab == SelectedTab::LoadTest {
-                            if self.load_test_url.edit_mode {
-                                self.load_test_url.add_char(c);
-                            }
+    fn poll_in_flight_request(&mut self) {
+        let finished = self
+            .in_flight_request
+            .as_ref()
+            .is_some_and(|request| request.handle.is_finished());
+        if !finished {
+            return;
+        }
 
-                            if c == 'e' && !self.load_test_url.edit_mode {
-                                self.load_test_url.toggle_mode();
-                            }
+        let request = self.in_flight_request.take().unwrap();
+        let elapsed_ms = request.started_at.elapsed().as_millis() as u64;
+        if !request.request.no_cookies
+            && self.cookies.is_enabled()
+            && let Err(e) = self.cookies.save()
+        {
+            self.status_message = Some(format!("Could not save cookies: {}", e));
+        }
+        let summary = match self.runtime.block_on(request.handle) {
+            Ok(Ok(output)) => {
+                let redirects = request.request.redirects;
+                if redirects.follow
+                    && output.status.is_redirection()
+                    && output.exchanges.len() > redirects.max_hops
+                    && output.headers.iter().any(|(name, _)| name == "location")
+                {
+                    self.status_message = Some(format!(
+                        "Stopped after {} redirects, press [R] to allow more",
+                        redirects.max_hops
+                    ));
+                }
+                // The response is shown right away, the TLS details take a
+                // second handshake.
+                self.abort_tls_inspection();
+                self.tls_inspection = Some(self.runtime.spawn(tls_info::inspect(
+                    output.url.clone(),
+                    request.tls,
+                    request.proxy,
+                )));
+                let summary = ResponseSummary::from_response(&output);
+                self.response.update_value(output.body_text());
+                self.last_response = Some(output);
+                self.viewer.scroll = 0;
+                self.refresh_response_body();
+                summary
+            }
+            Ok(Err(message)) => {
+                self.response.update_value(message.clone());
+                self.last_response = None;
+                ResponseSummary::from_error(message, elapsed_ms)
+            }
+            Err(e) => {
+                let message = format!("Error while making request: {}", e);
+                self.response.update_value(message.clone());
+                self.last_response = None;
+                ResponseSummary::from_error(message, elapsed_ms)
+            }
+        };
 
-                            if c == 'r' && !self.load_test_url.edit_mode {
-                                let url = self.load_test_url.clone();
-                                let parsed_url = parse_into_https(&url.value);
-                                App::run_load_test(parsed_url, self.load_test_result.clone());
-                            }
-                        } else {
-                            for display_string in display_strings.iter_mut() {
-                                if display_string.edit_mode {
-                                    display_string.add_char(c);
-                                    break;
-                                }
-                            }
+        let mut saved = request.request;
+        saved.strip_secrets();
+        self.record_history(HistoryEntry {
+            sent_at: chrono::Local::now(),
+            request: saved,
+            sent: Some(request.sent),
+            response: summary,
+        });
+    }
 
-                            if c == 'e' {
-                                if self.active_block == 0 {
-                                    self.request_type.toggle()
-                                }
+    fn poll_tls_inspection(&mut self) {
+        if !self
+            .tls_inspection
+            .as_ref()
+            .is_some_and(|inspection| inspection.is_finished())
+        {
+            return;
+        }
 
-                                if self.active_block == 1 && !self.request_url.edit_mode {
-                                    self.request_url.toggle_mode();
-                                    if self.request_url.value == PLACEHOLDER_URL_VALUE.to_string() {
-                                        self.request_url.update_value(String::from(""));
-                                    }
-                                }
+        let inspection = self.tls_inspection.take().unwrap();
+        let tls = self
+            .runtime
+            .block_on(inspection)
+            .unwrap_or_else(|e| Some(Err(e.to_string())));
+        if let Some(Ok(tls)) = &tls
+            && let Some(warning) = tls.expiry_warning()
+            && self.status_message.is_none()
+        {
+            self.status_message = Some(format!("{}, see the TLS view", warning));
+        }
+        if let Some(response) = &mut self.last_response {
+            response.tls = tls;
+        }
+    }
 
-                                if self.active_block == 2 && !self.request_body.edit_mode {
-                                    self.request_body.toggle_mode();
-                                    if self.request_body.value
-                                        == PLACEHOLDER_REQUEST_BODY.to_string()
-                                    {
-                                        self.request_body.update_value(String::from(""));
-                                    }
-                                }
+    fn abort_tls_inspection(&mut self) {
+        if let Some(inspection) = self.tls_inspection.take() {
+            inspection.abort();
+        }
+    }
 
-                                if self.active_block == 3 && !self.headers.edit_mode {
-                                    self.headers.toggle_mode();
-                                }
-                            }
+    fn record_history(&mut self, entry: HistoryEntry) {
+        if let Err(e) = append_history(&entry) {
+            self.status_message = Some(format!("Could not save history: {}", e));
+        }
+        self.history.push(entry);
+    }
 
-                            if c == 'r'
-                                && !self.request_url.edit_mode
-                                && !self.request_body.edit_mode
-                                && !self.request_type.open
-                            {
-                                let url_path = parse_into_https(&self.request_url.value);
-                                let parsed_headers = build_headers(&self.headers.value).unwrap();
-                                let res = match self
-                                    .request_type
-                                    .get_selected_value()
-                                    .parse::<RequestType>()
-                                    .unwrap()
-                                {
-                                    RequestType::GET => {
-                                        self.client.get(url_path).headers(parsed_headers).send()
-                                    }
-                                    RequestType::POST => {
-                                        if !self
-                                            .request_body
-                                            .value
-                                            .contains(&PLACEHOLDER_REQUEST_BODY.to_string())
-                                        {
-                                            self.client
-                                                .post(&url_path)
-                                                .headers(parsed_headers)
-                                                .body(self.request_body.value.clone())
-                                                .send()
-                                        } else {
-                                            self.client
-                                                .post(&url_path)
-                                                .headers(parsed_headers)
-                                                .send()
-                                        }
-                                    }
-                                    RequestType::PUT => {
-                                        if !self
-                                            .request_body
-                                            .value
-                                            .contains(&PLACEHOLDER_REQUEST_BODY.to_string())
-                                        {
-                                            self.client
-                                                .put(&url_path)
-                                                .headers(parsed_headers)
-                                                .body(self.request_body.value.clone())
-                                                .send()
-                                        } else {
-                                            self.client
-                                                .put(&url_path)
-                                                .headers(parsed_headers)
-                                                .send()
-                                        }
-                                    }
-                                    RequestType::PATCH => {
-                                        if !self
-                                            .request_body
-                                            .value
-                                            .contains(&PLACEHOLDER_REQUEST_BODY.to_string())
-                                        {
-                                            self.client
-                                                .patch(&url_path)
-                                                .headers(parsed_headers)
-                                                .body(self.request_body.value.clone())
-                                                .send()
-                                        } else {
-                                            self.client
-                                                .patch(&url_path)
-                                                .headers(parsed_headers)
-                                                .send()
-                                        }
-                                    }
-                                    RequestType::DELETE => {
-                                        if !self
-                                            .request_body
-                                            .value
-                                            .contains(&PLACEHOLDER_REQUEST_BODY.to_string())
-                                        {
-                                            self.client
-                                                .delete(&url_path)
-                                                .headers(parsed_headers)
-                                                .body(self.request_body.value.clone())
-                                                .send()
-                                        } else {
-                                            self.client
-                                                .delete(&url_path)
-                                                .headers(parsed_headers)
-                                                .send()
-                                        }
-                                    }
-                                };
+    /// Snapshot of the editor, leaving out placeholder values.
+    fn current_request(&mut self) -> SavedRequest {
+        let body = if self.request_body.value == PLACEHOLDER_REQUEST_BODY {
+            String::new()
+        } else {
+            self.request_body.value.clone()
+        };
 
-                                match res {
-                                    Ok(output) => {
-                                        if output.status().is_success() {
-                                            self.response.update_value(output.text().unwrap());
-                                        } else {
-                                            self.response.update_value(format!(
-                                                "Status code: {}, Error message: {}",
-                                                output.status(),
-                                                output.text().unwrap_or_else(|_| {
-                                                    "No response body".to_string()
-                                                }),
-                                            ));
-                                        }
-                                    }
-                                    Err(e) => {
-                                        self.response.update_value(format!(
-                                            "Error while making request: {}",
-                                            e
-                                        ));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Backspace => {
-                        if self.selected_tab == SelectedTab::LoadTest {
-                            self.load_test_url.remove_last_char();
-                        } else {
-                            for display_string in display_strings.iter_mut() {
-                                if display_string.edit_mode {
-                                    display_string.remove_last_char();
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Down | KeyCode::Right => {
-                        if self.request_type.open {
-                            self.request_type.next();
-                        } else if self.request_url.edit_mode
-                            || self.request_body.edit_mode
-                            || self.headers.edit_mode
-                        {
-                        } else {
-                            self.active_block =
-                                cmp::min(self.active_block + 1, self.block_size - 1);
-                        }
-                    }
-                    KeyCode::Up | KeyCode::Left => {
-                        if self.request_type.open {
-                            self.request_type.previous();
-                        } else if self.request_url.edit_mode
-                            || self.request_body.edit_mode
-                            || self.headers.edit_mode
-                        {
-                        } else {
-                            self.active_block = if self.active_block == 0 {
-                                0
-                            } else {
-                                self.active_block - 1
-                            }
-                        }
-                    }
-                    KeyCode::Enter | KeyCode::Esc => {
-                        if self.selected_tab == SelectedTab::LoadTest {
-                            self.load_test_url.toggle_mode();
-                        } else {
-                            if self.request_type.open {
-                                self.request_type.toggle();
-                            }
+        SavedRequest {
+            method: self.request_type.get_selected_value(),
+            url: self.request_url.value.clone(),
+            headers: self.headers.rows.clone(),
+            body,
+            insecure: self.insecure,
+            disabled_params: self
+                .params
+                .rows
+                .iter()
+                .filter(|param| !param.enabled)
+                .cloned()
+                .collect(),
+            body_mode: self.body_mode,
+            form: self.form.rows.clone(),
+            body_file: self.body_file.value.clone(),
+            auth: self.auth.auth.clone(),
+            no_cookies: self.no_cookies,
+            redirects: self.redirects,
+        }
+    }
 
-                            for display_string in display_strings.iter_mut() {
-                                if display_string.edit_mode {
-                                    display_string.toggle_mode();
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+    fn load_request(&mut self, request: &SavedRequest) {
+        self.request_type.select_value(&request.method);
+        self.request_url.update_value(request.url.clone());
+        let mut params = query_params(&request.url);
+        params.extend(request.disabled_params.iter().cloned());
+        self.params.set_rows(params);
+        self.params_url = request.url.clone();
+        self.headers.set_rows(request.headers.clone());
+        self.auth.set_auth(request.auth.clone());
+        self.set_body_mode(request.body_mode);
+        self.form.set_rows(request.form.clone());
+        self.body_file.update_value(request.body_file.clone());
+        self.body_file.edit_mode = false;
+        self.insecure = request.insecure;
+        self.no_cookies = request.no_cookies;
+        self.redirects = request.redirects;
+        if request.body.is_empty() {
+            self.request_body
+                .update_value(PLACEHOLDER_REQUEST_BODY.to_string());
+        } else {
+            self.request_body.update_value(request.body.clone());
+        }
+        self.selected_tab = SelectedTab::RequestReply;
+        self.active_block = 1;
+        self.opened_http_request = None;
+        self.http_file_variables.clear();
+    }
+
+    fn set_body_mode(&mut self, mode: BodyMode) {
+        self.body_mode = mode;
+        match mode {
+            BodyMode::FormUrlencoded => self.form.set_kind(TableKind::FormUrlencoded),
+            BodyMode::Multipart => self.form.set_kind(TableKind::Multipart),
+            BodyMode::Raw | BodyMode::File => {}
+        }
+    }
+
+    /// Keys for the Request Body block. `m` switches the body mode, the form
+    /// modes hand everything else to their table.
+    fn handle_body_key(&mut self, code: KeyCode) -> bool {
+        if self.request_body.edit_mode {
+            return false;
+        }
+        if code == KeyCode::Char('m') && !self.form.is_editing() && !self.body_file.edit_mode {
+            self.set_body_mode(self.body_mode.next());
+            return true;
+        }
+
+        match self.body_mode {
+            BodyMode::Raw => false,
+            BodyMode::FormUrlencoded | BodyMode::Multipart => self.form.handle_key(code),
+            BodyMode::File => self.handle_body_file_key(code),
+        }
+    }
+
+    /// Editing the path of the file body, `tab` completes it from disk.
+    fn handle_body_file_key(&mut self, code: KeyCode) -> bool {
+        if !self.body_file.edit_mode {
+            if matches!(code, KeyCode::Char('e') | KeyCode::Enter) {
+                self.body_file.toggle_mode();
+                return true;
             }
+            return false;
         }
 
-        Ok(())
+        match code {
+            KeyCode::Char(c) => self.body_file.add_char(c),
+            KeyCode::Backspace => self.body_file.remove_last_char(),
+            KeyCode::Tab => {
+                if let Some(completion) = path_completions(&self.body_file.value).first() {
+                    self.body_file.update_value(completion.clone());
+                }
+            }
+            KeyCode::Enter | KeyCode::Esc => self.body_file.toggle_mode(),
+            _ => {}
+        }
+        true
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
-        let titles: Vec<Span> = SelectedTab::all()
+    /// Re-reads the Params table after the URL was edited as text. Disabled
+    /// rows aren't in the URL, so they're kept where they were.
+    fn sync_params_from_url(&mut self) {
+        if self.request_url.value == self.params_url {
+            return;
+        }
+        self.params_url = self.request_url.value.clone();
+
+        let mut parsed = query_params(&self.params_url).into_iter();
+        let mut rows: Vec<KeyValue> = self
+            .params
+            .rows
             .iter()
-            .map(|t| {
-                Span::from(Span::styled(
-                    t.to_string(),
-                    Style::default().fg(Color::White),
-                ))
+            .filter_map(|row| {
+                if row.enabled {
+                    parsed.next()
+                } else {
+                    Some(row.clone())
+                }
             })
             .collect();
-        let selected_tab_index = self.selected_tab as usize;
-        let tabs = Tabs::new(titles)
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Green)
-                    .bg(Color::Black)
-                    .add_modifier(Modifier::BOLD),
-            )
-            .style(Style::default().fg(Color::Gray))
-            .select(selected_tab_index)
-            .padding("", "")
-            .divider(" ");
-        use Constraint::{Length, Min};
-        let vertical = Layout::vertical([Length(1), Min(0), Length(1)]);
-        let [header_area, inner_area, footer_area] = vertical.areas(frame.area());
+        rows.extend(parsed);
+        self.params.set_rows(rows);
+    }
 
-        let horizontal = Layout::horizontal([Min(0), Length(50)]);
-        let [tabs_area, title_area] = horizontal.areas(header_area);
-        frame.render_widget(self.selected_tab.description().bold(), title_area);
-        frame.render_widget(tabs, tabs_area);
-        match self.selected_tab {
-            SelectedTab::RequestReply => {
-                self.render_request_reply_tab(frame, inner_area);
-            }
-            SelectedTab::LoadTest => {
-                self.render_load_test_tab(frame, inner_area);
-            }
+    /// Rewrites the query of the URL after the Params table was edited. The
+    /// URL is left alone if the enabled rows still match it, so its encoding
+    /// isn't normalized for nothing.
+    fn sync_url_from_params(&mut self) {
+        let enabled: Vec<KeyValue> = self
+            .params
+            .rows
+            .iter()
+            .filter(|row| row.enabled && !(row.name.is_empty() && row.value.is_empty()))
+            .cloned()
+            .collect();
+        if enabled == query_params(&self.request_url.value) {
+            return;
         }
 
-        let footer_widget =
-            Line::raw("[h] Previous tab [l] Next tab [e] Edit [enter] Save/Exit edit mode [r] Request [q] Quit").centered();
-        frame.render_widget(footer_widget, footer_area);
+        let url = if self.request_url.value == PLACEHOLDER_URL_VALUE {
+            ""
+        } else {
+            &self.request_url.value
+        };
+        let url = with_query_params(url, &enabled);
+        self.request_url.update_value(url.clone());
+        self.params_url = url;
     }
 
-    fn render_request_reply_tab(&mut self, frame: &mut Frame, area: Rect) {
-        let vertical_constraints = [
-            Constraint::Percentage(7),
-            Constraint::Percentage(45),
-            Constraint::Percentage(45),
-        ];
-
-        let request_horizontal_constraints =
-            [Constraint::Percentage(20), Constraint::Percentage(80)];
-        let body_horizontal_contraints = [Constraint::Percentage(50), Constraint::Percentage(50)];
+    fn open_http_request(&mut self, path: PathBuf, index: usize) -> Result<()> {
+        let file = http_file::load(&path)?;
+        let request = file
+            .requests
+            .get(index)
+            .ok_or_else(|| eyre!("{} no longer has request {}", path.display(), index + 1))?;
+        if let Some(reason) = &request.unsupported {
+            return Err(eyre!("Can't open {}: {}", request.name, reason));
+        }
+        self.load_request(&request.request);
+        self.opened_request = None;
+        self.opened_http_request = Some((path, index));
+        self.http_file_variables = file.variables;
 
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(vertical_constraints.as_ref())
-            .split(area);
+        Ok(())
+    }
+
+    fn save_current_request(&mut self) {
+        let Some((path, index)) = self.opened_http_request.clone() else {
+            self.open_save_prompt();
+            return;
+        };
+        self.status_message = Some(
+            match http_file::save_request(&path, index, &self.current_request()) {
+                Ok(()) => format!("Saved to {}", path.display()),
+                Err(e) => format!("Could not save to {}: {}", path.display(), e),
+            },
+        );
+        self.refresh_collections();
+    }
+
+    fn handle_prompt_key(&mut self, code: KeyCode) {
+        let Some(prompt) = self.prompt.as_mut() else {
+            return;
+        };
+
+        match code {
+            KeyCode::Char(c) => prompt.input.add_char(c),
+            KeyCode::Backspace => prompt.input.remove_last_char(),
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                let prompt = self.prompt.take().unwrap();
+                if let Err(e) = self.submit_prompt(prompt) {
+                    self.status_message = Some(e.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn submit_prompt(&mut self, prompt: Prompt) -> Result<()> {
+        let value = prompt.input.value.trim().to_string();
+        match prompt.action {
+            PromptAction::SaveRequest => {
+                let path = collections::request_path(&value)?;
+                let request = self.current_request();
+                let stripped = collections::save_request(&path, &request)?;
+                self.status_message = Some(if stripped.is_empty() {
+                    format!("Saved {}", value)
+                } else {
+                    format!(
+                        "Saved {} without its secrets ({}), use {{{{variables}}}} to keep them",
+                        value,
+                        stripped.join(", ")
+                    )
+                });
+                self.opened_request = Some(path);
+            }
+            PromptAction::NewFolder(parent) => {
+                let path = collections::create_folder(&parent, &value)?;
+                if let Some(parent) = path.parent() {
+                    self.collections_expanded.insert(parent.to_path_buf());
+                }
+            }
+            PromptAction::Rename(path, kind) => {
+                let new_path = collections::rename(&path, kind, &value)?;
+                if self.opened_request.as_ref() == Some(&path) {
+                    self.opened_request = Some(new_path.clone());
+                }
+                if let Some(opened) = self.http_files.iter_mut().find(|p| **p == path) {
+                    *opened = new_path.clone();
+                }
+                if let Some((opened, _)) = self.opened_http_request.as_mut()
+                    && *opened == path
+                {
+                    *opened = new_path;
+                }
+            }
+            PromptAction::ConfirmDelete(path, kind) => {
+                if value.eq_ignore_ascii_case("y") {
+                    collections::delete(&path, kind)?;
+                    if self.opened_request.as_ref() == Some(&path) {
+                        self.opened_request = None;
+                    }
+                }
+            }
+            PromptAction::ImportCurl => {
+                let request = curl::parse_curl(&value)?;
+                self.load_request(&request);
+                self.opened_request = None;
+                self.status_message = Some("Imported curl command".to_string());
+            }
+            PromptAction::ImportFile => {
+                let summary = import::import_file(&storage::expand_home(&value))?;
+                let mut message = format!(
+                    "Imported {} requests into {}",
+                    summary.requests, summary.collection
+                );
+                if let Some(environment) = summary.environment {
+                    message.push_str(&format!(", variables saved to environment {}", environment));
+                }
+                if !summary.stripped.is_empty() {
+                    message.push_str(&format!(
+                        ", left out the secrets of {}: {}",
+                        summary.stripped.len(),
+                        summary.stripped.join("; ")
+                    ));
+                }
+                if !summary.skipped.is_empty() {
+                    message.push_str(&format!(
+                        ", skipped {}: {}",
+                        summary.skipped.len(),
+                        summary.skipped.join("; ")
+                    ));
+                }
+                self.status_message = Some(message);
+                self.refresh_environments();
+            }
+            PromptAction::OpenHttpFile => {
+                let path = fs::canonicalize(storage::expand_home(&value))
+                    .map_err(|e| eyre!("Could not open {}: {}", value, e))?;
+                let file = http_file::load(&path)?;
+                if !self.http_files.contains(&path) {
+                    self.http_files.insert(0, path.clone());
+                }
+                self.collections_expanded.insert(path.clone());
+                self.status_message = Some(format!(
+                    "Opened {} with {} requests",
+                    path.display(),
+                    file.requests.len()
+                ));
+            }
+            PromptAction::ExportHar => {
+                let entries: Vec<&HistoryEntry> = self
+                    .filtered_history()
+                    .into_iter()
+                    .rev()
+                    .map(|i| &self.history[i])
+                    .collect();
+                har::export_har(&entries, &storage::expand_home(&value))?;
+                self.status_message =
+                    Some(format!("Exported {} requests to {}", entries.len(), value));
+            }
+            PromptAction::NewEnvironment => {
+                let environment = Environment {
+                    name: value.clone(),
+                    ..Default::default()
+                };
+                environment::save_environment(&environment)?;
+                self.refresh_environments();
+                self.select_environment(&value);
+            }
+            PromptAction::ConfirmDeleteEnvironment(name) => {
+                if value.eq_ignore_ascii_case("y") {
+                    environment::delete_environment(&name)?;
+                    if self.active_environment.as_ref() == Some(&name) {
+                        self.set_active_environment(None);
+                    }
+                    self.refresh_environments();
+                }
+            }
+            PromptAction::SetVariable(name, previous_key) => {
+                let (key, variable) = environment::parse_variable(&value)?;
+                let Some(environment) = self.environments.iter_mut().find(|e| e.name == name)
+                else {
+                    return Ok(());
+                };
+                if let Some(previous_key) = previous_key {
+                    environment.variables.remove(&previous_key);
+                }
+                environment.variables.insert(key, variable);
+                environment::save_environment(environment)?;
+            }
+            PromptAction::SetTls(name, field) => {
+                let Some(environment) = self.environments.iter_mut().find(|e| e.name == name)
+                else {
+                    return Ok(());
+                };
+                environment.tls.set_text(field, value);
+                environment::save_environment(environment)?;
+            }
+            PromptAction::SetProxy(name, field) => {
+                self.update_proxy(name.as_deref(), |proxy| proxy.set_text(field, value));
+            }
+            PromptAction::SetRedirects => {
+                let max_hops: usize = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| eyre!("Expected a number of hops, 0 to not follow"))?;
+                self.redirects = if max_hops == 0 {
+                    Redirects {
+                        follow: false,
+                        ..self.redirects
+                    }
+                } else {
+                    Redirects {
+                        follow: true,
+                        max_hops,
+                    }
+                };
+            }
+            PromptAction::SetCookie(previous) => {
+                let cookie = cookies::parse_cookie(&value, previous.as_ref())?;
+                self.cookies.set(cookie, previous.as_ref());
+                self.cookies.save()?;
+            }
+            PromptAction::ConfirmDeleteCookies(domain) => {
+                if value.eq_ignore_ascii_case("y") {
+                    self.cookies.remove_domain(&domain);
+                    self.cookies.save()?;
+                }
+            }
+        }
+        self.refresh_collections();
+
+        Ok(())
+    }
+
+    fn copy_as_curl(&mut self) {
+        let mut request = resolve_request(&self.current_request(), &self.active_variables());
+        // curl can't fetch the token itself, a cached one is copied instead.
+        if let Auth::OAuth2(oauth) = &request.auth
+            && let Some(token) = self.tokens.cached(oauth)
+        {
+            request
+                .headers
+                .retain(|h| !h.name.trim().eq_ignore_ascii_case("authorization"));
+            request
+                .headers
+                .push(KeyValue::new("Authorization", format!("Bearer {}", token)));
+        }
+        let command = curl::to_curl(&request, &parse_into_https(&request.url));
+        self.status_message = Some(match copy_to_clipboard(&command) {
+            Ok(()) => "Copied request as curl command".to_string(),
+            Err(e) => format!("Could not copy to clipboard: {}", e),
+        });
+    }
+
+    fn handle_paste(&mut self, text: &str) {
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.input.add_str(text);
+            return;
+        }
+
+        if self.params.is_editing() {
+            self.params.paste(text);
+            return;
+        }
+        if self.form.is_editing() {
+            self.form.paste(text);
+            return;
+        }
+        if self.headers.is_editing() {
+            self.headers.paste(text);
+            return;
+        }
+        if self.auth.is_editing() {
+            self.auth.paste(text);
+            return;
+        }
+
+        for display_string in [
+            &mut self.request_url,
+            &mut self.request_body,
+            &mut self.body_file,
+            &mut self.load_test_url,
+            &mut self.history_filter,
+            &mut self.viewer.search,
+        ] {
+            if display_string.edit_mode {
+                display_string.add_str(text);
+                break;
+            }
+        }
+    }
+
+    fn refresh_environments(&mut self) {
+        self.environments = environment::load_environments();
+        let selected = self.environments_state.selected().unwrap_or(0);
+        self.environments_state.select(Some(cmp::min(
+            selected,
+            self.environments.len().saturating_sub(1),
+        )));
+    }
+
+    fn select_environment(&mut self, name: &str) {
+        if let Some(i) = self.environments.iter().position(|e| e.name == name) {
+            self.environments_state.select(Some(i));
+        }
+    }
+
+    /// Variables from the active environment, plus the file variables when the
+    /// request came from a `.http` file.
+    fn active_variables(&self) -> BTreeMap<String, String> {
+        let mut variables = self
+            .environments
+            .iter()
+            .find(|e| Some(&e.name) == self.active_environment.as_ref())
+            .map(|e| e.variables.clone())
+            .unwrap_or_default();
+        http_file::resolve_variables(&self.http_file_variables, &mut variables);
+        variables
+    }
+
+    fn selected_environment(&self) -> Option<&Environment> {
+        self.environments.get(self.environments_state.selected()?)
+    }
+
+    /// The TLS settings of the active environment.
+    fn active_tls(&self) -> TlsSettings {
+        self.environments
+            .iter()
+            .find(|e| Some(&e.name) == self.active_environment.as_ref())
+            .map(|e| e.tls.clone())
+            .unwrap_or_default()
+    }
+
+    fn update_tls(&mut self, name: &str, update: impl FnOnce(&mut TlsSettings)) {
+        if let Some(environment) = self.environments.iter_mut().find(|e| e.name == name) {
+            update(&mut environment.tls);
+            if let Err(e) = environment::save_environment(environment) {
+                self.status_message = Some(e.to_string());
+            }
+        }
+    }
+
+    /// The proxy settings of the active environment, the global ones when it
+    /// has none.
+    fn active_proxy(&self) -> ProxySettings {
+        let environment = self
+            .environments
+            .iter()
+            .find(|e| Some(&e.name) == self.active_environment.as_ref());
+        proxy::effective(environment.map(|e| &e.proxy), &self.proxy)
+    }
+
+    /// Changes an environment's proxy settings, or the global ones when
+    /// there's no name.
+    fn update_proxy(&mut self, name: Option<&str>, update: impl FnOnce(&mut ProxySettings)) {
+        let saved = match name {
+            None => {
+                update(&mut self.proxy);
+                proxy::save_global(&self.proxy)
+            }
+            Some(name) => match self.environments.iter_mut().find(|e| e.name == name) {
+                Some(environment) => {
+                    update(&mut environment.proxy);
+                    environment::save_environment(environment)
+                }
+                None => Ok(()),
+            },
+        };
+        if let Err(e) = saved {
+            self.status_message = Some(e.to_string());
+        }
+    }
+
+    /// Switches environment, along with the cookie jar that belongs to it.
+    fn set_active_environment(&mut self, name: Option<String>) {
+        self.cookies = match CookieJar::load(name.as_deref()) {
+            Ok(cookies) => cookies,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                CookieJar::default()
+            }
+        };
+        self.cookies_state.select(Some(0));
+        self.active_environment = name;
+    }
+
+    /// Handles keys for the Cookies tab, returning whether the key was
+    /// consumed.
+    fn handle_cookies_key(&mut self, code: KeyCode) -> bool {
+        let cookies = self.cookies.cookies();
+        let selected = self.cookies_state.selected().unwrap_or(0);
+        let cookie = cookies.get(selected).cloned();
+
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cookies_state.select(Some(cmp::min(
+                    selected + 1,
+                    cookies.len().saturating_sub(1),
+                )));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.cookies_state.select(Some(selected.saturating_sub(1)));
+            }
+            KeyCode::Char('c') => {
+                self.cookies.set_enabled(!self.cookies.is_enabled());
+                if let Err(e) = self.cookies.save() {
+                    self.status_message = Some(format!("Could not save cookies: {}", e));
+                }
+            }
+            KeyCode::Char('n') => {
+                self.prompt = Some(Prompt::new(
+                    "New cookie (name=value; Domain=example.com; Path=/)",
+                    String::new(),
+                    PromptAction::SetCookie(None),
+                ));
+            }
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some(cookie) = cookie {
+                    self.prompt = Some(Prompt::new(
+                        "Edit cookie (name=value; Domain=example.com; Path=/)",
+                        cookie.to_set_cookie(),
+                        PromptAction::SetCookie(Some(cookie)),
+                    ));
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                if let Some(cookie) = cookie {
+                    self.cookies.remove(&cookie);
+                    if let Err(e) = self.cookies.save() {
+                        self.status_message = Some(format!("Could not save cookies: {}", e));
+                    }
+                }
+            }
+            KeyCode::Char('D') => {
+                if let Some(cookie) = cookie {
+                    self.prompt = Some(Prompt::new(
+                        &format!("Delete all cookies for {}? (y/n)", cookie.domain),
+                        String::new(),
+                        PromptAction::ConfirmDeleteCookies(cookie.domain),
+                    ));
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Handles keys for the Proxy block of the Environments tab, which edits
+    /// the selected environment's settings or the global ones.
+    fn handle_proxy_key(&mut self, code: KeyCode, environment: Option<String>) -> bool {
+        if code == KeyCode::Char('g') {
+            self.proxy_global = !self.proxy_global;
+            return true;
+        }
+        let name = if self.proxy_global {
+            None
+        } else {
+            match environment {
+                Some(name) => Some(name),
+                None => return false,
+            }
+        };
+        let proxy = match &name {
+            None => self.proxy.clone(),
+            Some(name) => self
+                .environments
+                .iter()
+                .find(|e| &e.name == name)
+                .map(|e| e.proxy.clone())
+                .unwrap_or_default(),
+        };
+
+        let selected = self.proxy_state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.proxy_state
+                    .select(Some(cmp::min(selected + 1, proxy::FIELDS.len() - 1)));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.proxy_state.select(Some(selected.saturating_sub(1)));
+            }
+            KeyCode::Char('e') | KeyCode::Enter | KeyCode::Char(' ')
+                if selected == proxy::ENVIRONMENT_VARIABLES =>
+            {
+                self.update_proxy(name.as_deref(), |proxy| {
+                    proxy.ignore_environment = !proxy.ignore_environment
+                });
+            }
+            KeyCode::Char('e') | KeyCode::Enter => {
+                self.prompt = Some(Prompt::new(
+                    proxy::FIELDS[selected],
+                    proxy.text(selected).to_string(),
+                    PromptAction::SetProxy(name, selected),
+                ));
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                self.update_proxy(name.as_deref(), |proxy| {
+                    if selected == proxy::ENVIRONMENT_VARIABLES {
+                        proxy.ignore_environment = false;
+                    } else {
+                        proxy.set_text(selected, String::new());
+                    }
+                });
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Handles keys for the Environments tab, returning whether the key was
+    /// consumed.
+    fn handle_environments_key(&mut self, code: KeyCode) -> bool {
+        let environment = self
+            .selected_environment()
+            .map(|e| (e.name.clone(), e.variables.clone(), e.tls.clone()));
+
+        if code == KeyCode::Tab {
+            self.environment_pane = self.environment_pane.next();
+            return true;
+        }
+        if code == KeyCode::Char('N') {
+            self.prompt = Some(Prompt::new(
+                "New environment name",
+                String::new(),
+                PromptAction::NewEnvironment,
+            ));
+            return true;
+        }
+        if self.environment_pane == EnvironmentPane::Proxy {
+            return self.handle_proxy_key(code, environment.map(|(name, _, _)| name));
+        }
+        let Some((name, variables, tls)) = environment else {
+            return false;
+        };
+
+        if self.environment_pane == EnvironmentPane::Tls {
+            let selected = self.tls_state.selected().unwrap_or(0);
+            match code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.tls_state
+                        .select(Some(cmp::min(selected + 1, tls::FIELDS.len() - 1)));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.tls_state.select(Some(selected.saturating_sub(1)));
+                }
+                KeyCode::Char('e') | KeyCode::Enter | KeyCode::Char(' ')
+                    if selected == tls::SKIP_VERIFICATION =>
+                {
+                    self.update_tls(&name, |tls| tls.skip_verification = !tls.skip_verification);
+                }
+                KeyCode::Char('e') | KeyCode::Enter => {
+                    self.prompt = Some(Prompt::new(
+                        tls::FIELDS[selected],
+                        tls.text(selected).to_string(),
+                        PromptAction::SetTls(name, selected),
+                    ));
+                }
+                KeyCode::Char('x') | KeyCode::Delete => {
+                    self.update_tls(&name, |tls| tls.set_text(selected, String::new()));
+                }
+                _ => return false,
+            }
+            return true;
+        }
+
+        if self.environment_pane == EnvironmentPane::Variables {
+            let selected = self.variables_state.selected().unwrap_or(0);
+            let variable = variables
+                .iter()
+                .nth(selected)
+                .map(|(k, v)| (k.clone(), v.clone()));
+            match code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.variables_state.select(Some(cmp::min(
+                        selected + 1,
+                        variables.len().saturating_sub(1),
+                    )));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.variables_state
+                        .select(Some(selected.saturating_sub(1)));
+                }
+                KeyCode::Char('n') => {
+                    self.prompt = Some(Prompt::new(
+                        "New variable (name=value)",
+                        String::new(),
+                        PromptAction::SetVariable(name, None),
+                    ));
+                }
+                KeyCode::Char('e') | KeyCode::Enter => {
+                    if let Some((key, value)) = variable {
+                        self.prompt = Some(Prompt::new(
+                            "Edit variable (name=value)",
+                            format!("{}={}", key, value),
+                            PromptAction::SetVariable(name, Some(key)),
+                        ));
+                    }
+                }
+                KeyCode::Char('x') | KeyCode::Delete => {
+                    if let Some((key, _)) = variable
+                        && let Some(environment) =
+                            self.environments.iter_mut().find(|e| e.name == name)
+                    {
+                        environment.variables.remove(&key);
+                        if let Err(e) = environment::save_environment(environment) {
+                            self.status_message = Some(e.to_string());
+                        }
+                    }
+                }
+                _ => return false,
+            }
+            return true;
+        }
+
+        let selected = self.environments_state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.environments_state.select(Some(cmp::min(
+                    selected + 1,
+                    self.environments.len().saturating_sub(1),
+                )));
+                self.variables_state.select(Some(0));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.environments_state
+                    .select(Some(selected.saturating_sub(1)));
+                self.variables_state.select(Some(0));
+            }
+            KeyCode::Enter => {
+                if self.active_environment.as_ref() == Some(&name) {
+                    self.set_active_environment(None);
+                } else {
+                    self.set_active_environment(Some(name));
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                self.prompt = Some(Prompt::new(
+                    &format!("Delete environment {}? (y/n)", name),
+                    String::new(),
+                    PromptAction::ConfirmDeleteEnvironment(name),
+                ));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn refresh_collections(&mut self) {
+        self.collection_nodes =
+            collections::load_tree(&self.collections_expanded, &self.http_files);
+        let selected = self.collections_state.selected().unwrap_or(0);
+        self.collections_state.select(Some(cmp::min(
+            selected,
+            self.collection_nodes.len().saturating_sub(1),
+        )));
+    }
+
+    fn open_save_prompt(&mut self) {
+        let location = self
+            .opened_request
+            .as_deref()
+            .map(collections::request_location)
+            .unwrap_or_default();
+        self.prompt = Some(Prompt::new(
+            "Save request as (collection/folder/name)",
+            location,
+            PromptAction::SaveRequest,
+        ));
+    }
+
+    /// Handles keys for the Collections tab, returning whether the key was
+    /// consumed.
+    fn handle_collections_key(&mut self, code: KeyCode) -> bool {
+        let selected = self.collections_state.selected().unwrap_or(0);
+        let node = self
+            .collection_nodes
+            .get(selected)
+            .map(|node| (node.path.clone(), node.kind, node.name.clone()));
+
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.collections_state.select(Some(cmp::min(
+                    selected + 1,
+                    self.collection_nodes.len().saturating_sub(1),
+                )));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.collections_state
+                    .select(Some(selected.saturating_sub(1)));
+            }
+            KeyCode::Enter => match node {
+                Some((path, NodeKind::Folder | NodeKind::HttpFile, _)) => {
+                    if !self.collections_expanded.remove(&path) {
+                        self.collections_expanded.insert(path);
+                    }
+                    self.refresh_collections();
+                }
+                Some((path, NodeKind::Request, _)) => match collections::load_request(&path) {
+                    Ok(request) => {
+                        self.load_request(&request);
+                        self.opened_request = Some(path);
+                    }
+                    Err(e) => {
+                        self.status_message =
+                            Some(format!("Could not open {}: {}", path.display(), e));
+                    }
+                },
+                Some((path, NodeKind::HttpRequest(index), _)) => {
+                    if let Err(e) = self.open_http_request(path, index) {
+                        self.status_message = Some(format!("Could not open request: {}", e));
+                    }
+                }
+                None => {}
+            },
+            KeyCode::Char('n') => {
+                let parent = match &node {
+                    Some((path, NodeKind::Folder, _)) => path.clone(),
+                    Some((path, _, _)) if path.starts_with(collections::collections_dir()) => path
+                        .parent()
+                        .map(|p| p.to_path_buf())
+                        .unwrap_or_else(collections::collections_dir),
+                    _ => collections::collections_dir(),
+                };
+                self.prompt = Some(Prompt::new(
+                    "New folder name",
+                    String::new(),
+                    PromptAction::NewFolder(parent),
+                ));
+            }
+            KeyCode::Char('N') => {
+                self.prompt = Some(Prompt::new(
+                    "New collection name",
+                    String::new(),
+                    PromptAction::NewFolder(collections::collections_dir()),
+                ));
+            }
+            KeyCode::Char('I') => {
+                self.prompt = Some(Prompt::new(
+                    "Import file (Postman collection, OpenAPI spec or HAR)",
+                    String::new(),
+                    PromptAction::ImportFile,
+                ));
+            }
+            KeyCode::Char('o') => {
+                self.prompt = Some(Prompt::new(
+                    "Open .http file",
+                    String::new(),
+                    PromptAction::OpenHttpFile,
+                ));
+            }
+            KeyCode::Char('r') => {
+                if let Some((path, kind, name)) = node
+                    && !matches!(kind, NodeKind::HttpRequest(_))
+                {
+                    self.prompt = Some(Prompt::new(
+                        "Rename to",
+                        name,
+                        PromptAction::Rename(path, kind),
+                    ));
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some((path, NodeKind::Request, _)) = node {
+                    if let Err(e) = collections::duplicate(&path) {
+                        self.status_message = Some(format!("Could not duplicate: {}", e));
+                    }
+                    self.refresh_collections();
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete => match node {
+                // Files opened from elsewhere are closed, not deleted.
+                Some((path, NodeKind::HttpFile, _)) if self.http_files.contains(&path) => {
+                    self.http_files.retain(|p| p != &path);
+                    self.refresh_collections();
+                }
+                Some((_, NodeKind::HttpRequest(_), _)) | None => {}
+                Some((path, kind, name)) => {
+                    self.prompt = Some(Prompt::new(
+                        &format!("Delete {}? (y/n)", name),
+                        String::new(),
+                        PromptAction::ConfirmDelete(path, kind),
+                    ));
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// Indices into `history` that match the filter, newest first.
+    fn filtered_history(&self) -> Vec<usize> {
+        (0..self.history.len())
+            .rev()
+            .filter(|&i| self.history[i].matches(&self.history_filter.value))
+            .collect()
+    }
+
+    fn selected_history_entry(&self) -> Option<&HistoryEntry> {
+        let selected = self.history_state.selected()?;
+        let index = *self.filtered_history().get(selected)?;
+        self.history.get(index)
+    }
+
+    /// Handles keys for the History tab, returning whether the key was consumed.
+    fn handle_history_key(&mut self, code: KeyCode) -> bool {
+        if self.history_filter.edit_mode {
+            match code {
+                KeyCode::Char(c) => self.history_filter.add_char(c),
+                KeyCode::Backspace => self.history_filter.remove_last_char(),
+                KeyCode::Enter | KeyCode::Esc => self.history_filter.toggle_mode(),
+                _ => {}
+            }
+            self.history_state.select(Some(0));
+            return true;
+        }
+
+        let entries = self.filtered_history().len();
+        let selected = self.history_state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Char('/') | KeyCode::Char('e') => self.history_filter.toggle_mode(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.history_state
+                    .select(Some(cmp::min(selected + 1, entries.saturating_sub(1))));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.history_state.select(Some(selected.saturating_sub(1)));
+            }
+            KeyCode::Enter => {
+                if let Some(entry) = self.selected_history_entry() {
+                    let request = entry.request.clone();
+                    self.load_request(&request);
+                }
+            }
+            KeyCode::Esc => {
+                self.history_filter.update_value(String::new());
+                self.history_state.select(Some(0));
+            }
+            KeyCode::Char('E') => {
+                self.prompt = Some(Prompt::new(
+                    "Export shown requests as HAR (file path)",
+                    "pingpong.har".to_string(),
+                    PromptAction::ExportHar,
+                ));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn cancel_request(&mut self) {
+        self.abort_tls_inspection();
+        if let Some(request) = self.in_flight_request.take() {
+            request.handle.abort();
+            self.response.update_value("Request cancelled".to_string());
+            self.last_response = None;
+        }
+    }
+
+    fn response_content_kind(&self) -> ContentKind {
+        let content_type = self.last_response.as_ref().and_then(|response| {
+            response
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                .map(|(_, value)| value.as_str())
+        });
+        ContentKind::from_content_type(content_type)
+    }
+
+    fn refresh_response_body(&mut self) {
+        self.response_body_lines = if self.response_raw {
+            raw_lines(&self.response.value)
+        } else {
+            format_body(&self.response.value, self.response_content_kind())
+        };
+        self.refresh_search();
+    }
+
+    fn refresh_search(&mut self) {
+        if let Some(response) = &self.last_response {
+            let lines = response_lines(response, self.response_view, &self.response_body_lines);
+            self.viewer.update_matches(&lines);
+        }
+    }
+
+    /// Handles keys meant for the Response viewer, returning whether the key
+    /// was consumed.
+    fn handle_viewer_key(&mut self, code: KeyCode) -> bool {
+        let Some(response) = &self.last_response else {
+            return false;
+        };
+
+        if self.viewer.search.edit_mode {
+            match code {
+                KeyCode::Char(c) => self.viewer.search.add_char(c),
+                KeyCode::Backspace => self.viewer.search.remove_last_char(),
+                KeyCode::Enter => {
+                    self.viewer.search.toggle_mode();
+                    return true;
+                }
+                KeyCode::Esc => {
+                    self.viewer.clear_search();
+                    return true;
+                }
+                _ => return true,
+            }
+            self.refresh_search();
+            return true;
+        }
+
+        let total_lines =
+            response_lines(response, self.response_view, &self.response_body_lines).len();
+        match code {
+            KeyCode::Char('j') => self.viewer.scroll_down(1, total_lines),
+            KeyCode::Char('k') => self.viewer.scroll_up(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.viewer.page_down(total_lines),
+            KeyCode::PageUp => self.viewer.page_up(),
+            KeyCode::Char('g') | KeyCode::Home => self.viewer.scroll = 0,
+            KeyCode::Char('G') | KeyCode::End => self.viewer.scroll_to_bottom(total_lines),
+            KeyCode::Char('w') => self.viewer.wrap = !self.viewer.wrap,
+            KeyCode::Char('#') => self.viewer.line_numbers = !self.viewer.line_numbers,
+            KeyCode::Char('/') => self.viewer.start_search(),
+            KeyCode::Char('n') => self.viewer.next_match(),
+            KeyCode::Char('N') => self.viewer.previous_match(),
+            KeyCode::Esc if !self.viewer.search.value.is_empty() => self.viewer.clear_search(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_events(&mut self) -> io::Result<()> {
+        if event::poll(Duration::from_millis(100))? {
+            let any_block_in_edit_mode = self.request_body.edit_mode
+                || self.request_url.edit_mode
+                || self.params.is_editing()
+                || self.form.is_editing()
+                || self.body_file.edit_mode
+                || self.headers.is_editing()
+                || self.auth.is_editing()
+                || self.load_test_url.edit_mode;
+
+            let event = event::read()?;
+            if let event::Event::Paste(text) = &event {
+                self.handle_paste(text);
+                return Ok(());
+            }
+
+            if let event::Event::Key(key) = event {
+                self.status_message = None;
+                if self.prompt.is_some() {
+                    self.handle_prompt_key(key.code);
+                    return Ok(());
+                }
+
+                if self.show_preview {
+                    if matches!(key.code, KeyCode::Char('v') | KeyCode::Esc | KeyCode::Enter) {
+                        self.show_preview = false;
+                    }
+                    return Ok(());
+                }
+
+                let consumed = match self.selected_tab {
+                    SelectedTab::RequestReply if self.active_block == 2 => {
+                        let consumed = self.params.handle_key(key.code);
+                        self.sync_url_from_params();
+                        consumed
+                    }
+                    SelectedTab::RequestReply if self.active_block == 3 => {
+                        self.auth.handle_key(key.code)
+                    }
+                    SelectedTab::RequestReply if self.active_block == 4 => {
+                        self.handle_body_key(key.code)
+                    }
+                    SelectedTab::RequestReply if self.active_block == 5 => {
+                        self.headers.handle_key(key.code)
+                    }
+                    SelectedTab::RequestReply if self.active_block == 6 => {
+                        self.handle_viewer_key(key.code)
+                    }
+                    SelectedTab::History => self.handle_history_key(key.code),
+                    SelectedTab::Collections => self.handle_collections_key(key.code),
+                    SelectedTab::Environments => self.handle_environments_key(key.code),
+                    SelectedTab::Cookies => self.handle_cookies_key(key.code),
+                    _ => false,
+                };
+                if consumed {
+                    return Ok(());
+                }
+            }
+
+            let previous_tab = self.selected_tab;
+            let mut display_strings = [&mut self.request_url, &mut self.request_body];
+            if let event::Event::Key(key) = event {
+                match key.code {
+                    KeyCode::Char(c) => {
+                        if c == 'h' && !any_block_in_edit_mode {
+                            self.selected_tab = self.selected_tab.previous();
+                        }
+
+                        if c == 'l' && !any_block_in_edit_mode {
+                            self.selected_tab = self.selected_tab.next();
+                        }
+
+                        if c == 'q' && !any_block_in_edit_mode {
+                            self.should_exit = true;
+                        }
+
+                        if self.selected_tab == SelectedTab::LoadTest {
+                            if self.load_test_url.edit_mode {
+                                self.load_test_url.add_char(c);
+                            }
+
+                            if c == 'e' && !self.load_test_url.edit_mode {
+                                self.load_test_url.toggle_mode();
+                            }
+
+                            if c == 'r' && !self.load_test_url.edit_mode {
+                                let url = self.load_test_url.clone();
+                                let parsed_url = parse_into_https(&url.value);
+                                // Load tests authenticate like the request in the
+                                // Request/Reply tab.
+                                let auth = self.auth.auth.resolve(&self.active_variables());
+                                match self.active_tls().load() {
+                                    Ok(tls) => App::run_load_test(
+                                        parsed_url,
+                                        auth,
+                                        tls,
+                                        self.active_proxy(),
+                                        self.tokens.clone(),
+                                        self.load_test_result.clone(),
+                                    ),
+                                    Err(e) => self
+                                        .load_test_result
+                                        .lock()
+                                        .unwrap()
+                                        .append_string(format!("Invalid TLS settings: {}", e)),
+                                }
+                            }
+                        } else if self.selected_tab == SelectedTab::RequestReply {
+                            for display_string in display_strings.iter_mut() {
+                                if display_string.edit_mode {
+                                    display_string.add_char(c);
+                                    break;
+                                }
+                            }
+
+                            if c == 'e' {
+                                if self.active_block == 0 {
+                                    self.request_type.toggle()
+                                }
+
+                                if self.active_block == 1 && !self.request_url.edit_mode {
+                                    self.request_url.toggle_mode();
+                                    if self.request_url.value == PLACEHOLDER_URL_VALUE {
+                                        self.request_url.update_value(String::from(""));
+                                    }
+                                }
+
+                                if self.active_block == 4 && !self.request_body.edit_mode {
+                                    self.request_body.toggle_mode();
+                                    if self.request_body.value == PLACEHOLDER_REQUEST_BODY {
+                                        self.request_body.update_value(String::from(""));
+                                    }
+                                }
+                            }
+
+                            if c == 'r' && !any_block_in_edit_mode && !self.request_type.open {
+                                self.send_request();
+                            }
+
+                            if c == 'c' && !any_block_in_edit_mode {
+                                self.cancel_request();
+                            }
+
+                            if c == 's' && !any_block_in_edit_mode && !self.request_type.open {
+                                self.save_current_request();
+                            }
+
+                            if c == 'v' && !any_block_in_edit_mode && !self.request_type.open {
+                                self.show_preview = true;
+                            }
+
+                            if c == 'i' && !any_block_in_edit_mode && !self.request_type.open {
+                                self.prompt = Some(Prompt::new(
+                                    "Paste a curl command",
+                                    String::new(),
+                                    PromptAction::ImportCurl,
+                                ));
+                            }
+
+                            if c == 'y' && !any_block_in_edit_mode && !self.request_type.open {
+                                self.copy_as_curl();
+                            }
+
+                            if c == 'K' && !any_block_in_edit_mode && !self.request_type.open {
+                                self.insecure = !self.insecure;
+                            }
+
+                            if c == 'C' && !any_block_in_edit_mode && !self.request_type.open {
+                                self.no_cookies = !self.no_cookies;
+                            }
+
+                            if c == 'R' && !any_block_in_edit_mode && !self.request_type.open {
+                                let max_hops = if self.redirects.follow {
+                                    self.redirects.max_hops
+                                } else {
+                                    0
+                                };
+                                self.prompt = Some(Prompt::new(
+                                    "Follow redirects, max hops (0 to not follow)",
+                                    max_hops.to_string(),
+                                    PromptAction::SetRedirects,
+                                ));
+                            }
+
+                            if c == 'p' && !any_block_in_edit_mode {
+                                self.response_raw = !self.response_raw;
+                                self.refresh_response_body();
+                            }
+                        }
+                    }
+                    KeyCode::Tab
+                        if self.selected_tab == SelectedTab::RequestReply
+                            && !any_block_in_edit_mode =>
+                    {
+                        self.response_view = self.response_view.next();
+                        self.viewer.scroll = 0;
+                        self.refresh_search();
+                    }
+                    KeyCode::Backspace => {
+                        if self.selected_tab == SelectedTab::LoadTest {
+                            self.load_test_url.remove_last_char();
+                        } else {
+                            for display_string in display_strings.iter_mut() {
+                                if display_string.edit_mode {
+                                    display_string.remove_last_char();
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    KeyCode::Down | KeyCode::Right => {
+                        if self.request_type.open {
+                            self.request_type.next();
+                        } else if self.request_url.edit_mode || self.request_body.edit_mode {
+                        } else {
+                            self.active_block =
+                                cmp::min(self.active_block + 1, self.block_size - 1);
+                        }
+                    }
+                    KeyCode::Up | KeyCode::Left => {
+                        if self.request_type.open {
+                            self.request_type.previous();
+                        } else if self.request_url.edit_mode || self.request_body.edit_mode {
+                        } else {
+                            self.active_block = if self.active_block == 0 {
+                                0
+                            } else {
+                                self.active_block - 1
+                            }
+                        }
+                    }
+                    KeyCode::Enter | KeyCode::Esc => {
+                        if self.selected_tab == SelectedTab::LoadTest {
+                            self.load_test_url.toggle_mode();
+                        } else {
+                            if self.request_type.open {
+                                self.request_type.toggle();
+                            }
+
+                            for display_string in display_strings.iter_mut() {
+                                if display_string.edit_mode {
+                                    display_string.toggle_mode();
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if self.selected_tab != previous_tab {
+                match self.selected_tab {
+                    SelectedTab::Collections => self.refresh_collections(),
+                    SelectedTab::Environments => self.refresh_environments(),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let titles: Vec<Span> = SelectedTab::all()
+            .iter()
+            .map(|t| Span::styled(t.to_string(), Style::default().fg(Color::White)))
+            .collect();
+        let selected_tab_index = self.selected_tab as usize;
+        let tabs = Tabs::new(titles)
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Green)
+                    .bg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .style(Style::default().fg(Color::Gray))
+            .select(selected_tab_index)
+            .padding("", "")
+            .divider(" ");
+        use Constraint::{Length, Min};
+        let vertical = Layout::vertical([Length(1), Min(0), Length(1)]);
+        let [header_area, inner_area, footer_area] = vertical.areas(frame.area());
+
+        let description = self.selected_tab.description();
+        let horizontal = Layout::horizontal([Min(0), Length(description.chars().count() as u16)]);
+        let [tabs_area, title_area] = horizontal.areas(header_area);
+        frame.render_widget(description.bold(), title_area);
+        frame.render_widget(tabs, tabs_area);
+        match self.selected_tab {
+            SelectedTab::RequestReply => {
+                self.render_request_reply_tab(frame, inner_area);
+            }
+            SelectedTab::LoadTest => {
+                self.render_load_test_tab(frame, inner_area);
+            }
+            SelectedTab::History => {
+                self.render_history_tab(frame, inner_area);
+            }
+            SelectedTab::Collections => {
+                self.render_collections_tab(frame, inner_area);
+            }
+            SelectedTab::Environments => {
+                self.render_environments_tab(frame, inner_area);
+            }
+            SelectedTab::Cookies => {
+                self.render_cookies_tab(frame, inner_area);
+            }
+        }
+
+        let footer_text = if let Some(message) = &self.status_message {
+            message.as_str()
+        } else if self.prompt.is_some() {
+            "[enter] Confirm [esc] Cancel"
+        } else if self.show_preview {
+            "[v] Close preview"
+        } else if self.selected_tab == SelectedTab::Environments
+            && self.environment_pane == EnvironmentPane::Tls
+        {
+            "[h] Previous tab [l] Next tab [tab] Switch list [j/k] Select [e/enter] Edit/Switch [x] Clear [q] Quit"
+        } else if self.selected_tab == SelectedTab::Environments
+            && self.environment_pane == EnvironmentPane::Proxy
+        {
+            "[h] Previous tab [l] Next tab [tab] Switch list [j/k] Select [e/enter] Edit/Switch [x] Clear [g] Global/environment [q] Quit"
+        } else if self.selected_tab == SelectedTab::Environments {
+            "[h] Previous tab [l] Next tab [tab] Switch list [enter] Activate/Edit [N] New environment [n] New variable [e] Edit variable [x] Delete [q] Quit"
+        } else if self.selected_tab == SelectedTab::Cookies {
+            "[h] Previous tab [l] Next tab [j/k] Select [c] Turn cookies on/off [n] New cookie [e/enter] Edit [x] Delete [D] Delete domain [q] Quit"
+        } else if self.selected_tab == SelectedTab::Collections {
+            "[h] Previous tab [l] Next tab [j/k] Select [enter] Open/Expand [N] New collection [n] New folder [r] Rename [d] Duplicate [x] Delete/Close [I] Import [o] Open .http file [q] Quit"
+        } else if self.selected_tab == SelectedTab::History {
+            "[h] Previous tab [l] Next tab [j/k] Select [/] Filter [esc] Clear filter [enter] Open in editor [E] Export HAR [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply && self.params.is_editing() {
+            "[enter] Next/Done [esc] Cancel"
+        } else if self.selected_tab == SelectedTab::RequestReply
+            && (self.form.is_editing() || self.headers.is_editing())
+        {
+            "[enter] Next/Done [tab] Accept suggestion [up/down] Cycle suggestions [esc] Cancel"
+        } else if self.selected_tab == SelectedTab::RequestReply && self.auth.is_editing() {
+            "[enter] Done [esc] Cancel"
+        } else if self.selected_tab == SelectedTab::RequestReply && self.active_block == 3 {
+            "[j/k] Select [e/enter] Edit/Switch [t] Auth type [*] Show/Hide secrets [r] Request [s] Save [v] Preview [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply && self.active_block == 2 {
+            "[j/k] Select parameter [e/enter] Edit [a] Add [x] Delete [space] Enable/Disable [r] Request [s] Save [v] Preview [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply && self.body_file.edit_mode {
+            "[tab] Complete path [enter] Done"
+        } else if self.selected_tab == SelectedTab::RequestReply
+            && self.active_block == 4
+            && self.body_mode == BodyMode::File
+        {
+            "[e/enter] Pick file [m] Body mode [r] Request [c] Cancel [s] Save [v] Preview [y] Copy as curl [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply
+            && self.active_block == 4
+            && self.body_mode == BodyMode::Multipart
+        {
+            "[j/k] Select field [e/enter] Edit [a] Add [x] Delete [space] Enable/Disable [f] Text/File [m] Body mode [r] Request [s] Save [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply
+            && self.active_block == 4
+            && self.body_mode == BodyMode::FormUrlencoded
+        {
+            "[j/k] Select field [e/enter] Edit [a] Add [x] Delete [space] Enable/Disable [m] Body mode [r] Request [s] Save [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply
+            && self.active_block == 4
+            && !self.request_body.edit_mode
+        {
+            "[e] Edit [m] Body mode [r] Request [c] Cancel [s] Save [v] Preview [i] Import curl [y] Copy as curl [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply && self.active_block == 5 {
+            "[j/k] Select header [e/enter] Edit [a] Add [x] Delete [space] Enable/Disable [r] Request [s] Save [v] Preview [q] Quit"
+        } else if self.selected_tab == SelectedTab::RequestReply
+            && self.active_block == 6
+            && self.last_response.is_some()
+        {
+            "[j/k] Scroll [PgUp/PgDn] Page [g/G] Top/Bottom [w] Wrap [#] Line numbers [/] Search [n/N] Next/Previous match [tab] Response view [p] Pretty/Raw"
+        } else {
+            "[h] Previous tab [l] Next tab [e] Edit [enter] Save/Exit edit mode [r] Request [c] Cancel [s] Save [v] Preview [i] Import curl [y] Copy as curl [K] Toggle TLS verification [C] Toggle cookies [R] Redirects [tab] Response view [p] Pretty/Raw [q] Quit"
+        };
+        let footer_widget = Line::raw(footer_text).centered();
+        frame.render_widget(footer_widget, footer_area);
+
+        if self.show_preview {
+            self.render_preview(frame);
+        }
+
+        if let Some(prompt) = &self.prompt {
+            let width = frame.area().width * 60 / 100;
+            let text_lines =
+                prompt.input.value.chars().count() / width.saturating_sub(2).max(1) as usize + 1;
+            let height = cmp::min(text_lines as u16, frame.area().height / 2) + 2;
+            let area = centered_rect(60, height, frame.area());
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                generate_paragraph(&prompt.input, prompt.title.clone(), true)
+                    .wrap(Wrap { trim: false }),
+                area,
+            );
+        }
+    }
+
+    fn render_request_reply_tab(&mut self, frame: &mut Frame, area: Rect) {
+        let vertical_constraints = [
+            Constraint::Percentage(7),
+            Constraint::Percentage(16),
+            Constraint::Percentage(35),
+            Constraint::Percentage(42),
+        ];
+
+        let request_horizontal_constraints =
+            [Constraint::Percentage(20), Constraint::Percentage(80)];
+        let params_horizontal_constraints =
+            [Constraint::Percentage(60), Constraint::Percentage(40)];
+        let body_horizontal_contraints = [Constraint::Percentage(50), Constraint::Percentage(50)];
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vertical_constraints.as_ref())
+            .split(area);
 
         let request_horizontal_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(request_horizontal_constraints.as_ref())
             .split(chunks[0]);
 
+        let params_horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(params_horizontal_constraints)
+            .split(chunks[1]);
+
         let body_horizontal_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(body_horizontal_contraints)
-            .split(chunks[1]);
+            .split(chunks[2]);
 
         let request_type_block_title = if self.request_type.open {
             "Request Type - editing"
@@ -591,51 +2229,807 @@ impl App {
                 .map(|item| ListItem::new(Span::raw(item.clone())))
                 .collect();
 
-            let list = List::new(items)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title(request_type_block_title),
-                )
-                .highlight_style(Style::default().fg(Color::Yellow));
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(request_type_block_title),
+                )
+                .highlight_style(Style::default().fg(Color::Yellow));
+
+            frame.render_stateful_widget(
+                list,
+                request_horizontal_chunks[0],
+                &mut self.request_type.state,
+            );
+        } else {
+            let selected_value = DisplayString::new(self.request_type.get_selected_value());
+            let request_type_block = generate_paragraph(
+                &selected_value,
+                "Request Method".to_string(),
+                self.active_block == 0,
+            );
+
+            frame.render_widget(request_type_block, request_horizontal_chunks[0]);
+        }
+
+        let mut url_title = match &self.active_environment {
+            Some(name) => format!("URL - env: {}", name),
+            None => "URL".to_string(),
+        };
+        if let Some((path, _)) = &self.opened_http_request
+            && let Some(file_name) = path.file_name()
+        {
+            url_title.push_str(&format!(" - {}", file_name.to_string_lossy()));
+        }
+        if self.insecure {
+            url_title.push_str(" - TLS verification off");
+        }
+        if self.no_cookies {
+            url_title.push_str(" - cookies off");
+        }
+        if !self.redirects.is_default() {
+            url_title.push_str(&format!(" - redirects: {}", self.redirects.label()));
+        }
+        let url_block = generate_paragraph(&self.request_url, url_title, self.active_block == 1);
+        frame.render_widget(url_block, request_horizontal_chunks[1]);
+
+        match self.body_mode {
+            BodyMode::Raw => {
+                let request_body_block = generate_paragraph(
+                    &self.request_body,
+                    format!("Request Body - {}", self.body_mode.label()),
+                    self.active_block == 4,
+                );
+                frame.render_widget(request_body_block, body_horizontal_chunks[0]);
+            }
+            BodyMode::FormUrlencoded | BodyMode::Multipart => {
+                self.form
+                    .render(frame, body_horizontal_chunks[0], self.active_block == 4);
+            }
+            BodyMode::File => self.render_body_file(frame, body_horizontal_chunks[0]),
+        }
+
+        self.params
+            .render(frame, params_horizontal_chunks[0], self.active_block == 2);
+        self.auth
+            .render(frame, params_horizontal_chunks[1], self.active_block == 3);
+        self.headers
+            .render(frame, body_horizontal_chunks[1], self.active_block == 5);
+
+        self.render_response(frame, chunks[3]);
+    }
+
+    /// The file body: its path, and the size and Content-Type it will be sent
+    /// with.
+    fn render_body_file(&self, frame: &mut Frame, area: Rect) {
+        let path_block = generate_paragraph(
+            &self.body_file,
+            format!("Request Body - {}", self.body_mode.label()),
+            self.active_block == 4,
+        );
+        frame.render_widget(path_block, area);
+
+        let resolved = environment::substitute(&self.body_file.value, &self.active_variables());
+        let path = storage::expand_home(resolved.trim());
+        let dim = Style::default().fg(Color::DarkGray);
+        let mut lines = vec![Line::raw("")];
+        if resolved.trim().is_empty() {
+            lines.push(Line::styled("Press [e] to pick a file to send", dim));
+        } else {
+            match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => {
+                    lines.push(Line::raw(format!(
+                        "Size: {}",
+                        format_size(metadata.len() as usize)
+                    )));
+                    let content_type = match header_value(&self.headers.rows, "content-type") {
+                        Some(content_type) => format!("{} (from the headers)", content_type),
+                        None => format!("{} (inferred)", content_type_for(&path)),
+                    };
+                    lines.push(Line::raw(format!("Content-Type: {}", content_type)));
+                }
+                Ok(_) => lines.push(Line::styled(
+                    format!("{} is not a file", path.display()),
+                    Style::default().fg(Color::LightRed),
+                )),
+                Err(e) => lines.push(Line::styled(
+                    format!("{}: {}", path.display(), e),
+                    Style::default().fg(Color::LightRed),
+                )),
+            }
+        }
+
+        let info_area = area.inner(Margin::new(1, 1));
+        let [_, info_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(info_area);
+        frame.render_widget(Paragraph::new(lines), info_area);
+    }
 
-            frame.render_stateful_widget(
-                list,
-                request_horizontal_chunks[0],
-                &mut self.request_type.state,
+    fn render_response(&mut self, frame: &mut Frame, area: Rect) {
+        if let Some(request) = &self.in_flight_request {
+            let elapsed = request.started_at.elapsed();
+            let frame_index = (elapsed.as_millis() / 100) as usize % SPINNER_FRAMES.len();
+            let sending = DisplayString::new(format!(
+                "{} Sending request... {:.1}s",
+                SPINNER_FRAMES[frame_index],
+                elapsed.as_secs_f64()
+            ));
+            let sending_block = generate_paragraph(
+                &sending,
+                "Response - [c] Cancel".to_string(),
+                self.active_block == 6,
             );
-        } else {
-            let selected_value = DisplayString::new(self.request_type.get_selected_value());
-            let request_type_block = generate_paragraph(
-                &selected_value,
-                "Request Method".to_string(),
-                self.active_block == 0,
+            frame.render_widget(sending_block, area);
+            return;
+        }
+
+        let Some(response) = &self.last_response else {
+            let response_body_block = generate_paragraph(
+                &self.response,
+                "Response".to_string(),
+                self.active_block == 6,
             );
+            frame.render_widget(response_body_block, area);
+            return;
+        };
 
-            frame.render_widget(request_type_block, request_horizontal_chunks[0]);
-        }
+        let mut title = vec![Span::raw("Response - ")];
+        title.push(Span::styled(
+            response.status.to_string(),
+            Style::default()
+                .fg(status_color(response.status))
+                .add_modifier(Modifier::BOLD),
+        ));
+        title.push(Span::raw(format!(
+            " · {} ms · {} ",
+            response.elapsed.as_millis(),
+            format_size(response.size())
+        )));
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Line::from(title))
+            .title_style(
+                Style::default()
+                    .fg(Color::LightYellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .border_style(if self.active_block == 6 {
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::LightBlue)
+            });
+        let inner_area = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [views_area, content_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner_area);
+        let content_kind = self.response_content_kind();
+        let views = Tabs::new(ResponseView::all().iter().map(|v| match v {
+            ResponseView::Body if content_kind != ContentKind::Text => {
+                format!(
+                    "{} ({})",
+                    v,
+                    if self.response_raw { "raw" } else { "pretty" }
+                )
+            }
+            ResponseView::Exchange if response.exchanges.len() > 1 => {
+                format!("{} ({})", v, response.exchanges.len())
+            }
+            ResponseView::Tls
+                if response.tls.as_ref().is_some_and(|tls| {
+                    tls.as_ref().is_ok_and(|tls| tls.expiry_warning().is_some())
+                }) =>
+            {
+                format!("{} ⚠", v)
+            }
+            _ => v.to_string(),
+        }))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        )
+        .style(Style::default().fg(Color::Gray))
+        .select(self.response_view as usize);
+        frame.render_widget(views, views_area);
+
+        let lines = response_lines(response, self.response_view, &self.response_body_lines);
+        self.viewer.render(frame, content_area, &lines);
+    }
 
-        let url_block =
-            generate_paragraph(&self.request_url, "URL".to_string(), self.active_block == 1);
-        frame.render_widget(url_block, request_horizontal_chunks[1]);
+    fn render_preview(&mut self, frame: &mut Frame) {
+        let variables = self.active_variables();
+        let request = self.current_request();
+        let resolved = resolve_request(&request, &variables);
+        let unresolved = environment::unresolved_request_variables(&request, &variables);
 
-        let request_body_block = generate_paragraph(
-            &self.request_body,
-            "Request Body".to_string(),
-            self.active_block == 2,
+        let heading = |label: &str| {
+            Line::styled(
+                label.to_string(),
+                Style::default()
+                    .fg(Color::LightCyan)
+                    .add_modifier(Modifier::BOLD),
+            )
+        };
+        let mut lines = vec![
+            Line::styled(
+                format!("{} {}", resolved.method, parse_into_https(&resolved.url)),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Line::raw(""),
+            heading("Headers"),
+        ];
+        if let Ok(url) = reqwest::Url::parse(&parse_into_https(&resolved.url))
+            && let Some(proxy) = self.active_proxy().route(&url)
+        {
+            lines.insert(
+                1,
+                Line::styled(
+                    format!(
+                        "Through the {} proxy {}:{}",
+                        proxy.scheme(),
+                        proxy.host_str().unwrap_or_default(),
+                        proxy.port_or_known_default().unwrap_or(1080)
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            );
+        }
+        lines.extend(header_lines(&resolved.headers));
+        lines.extend(auth_line(&resolved));
+        if let Auth::OAuth2(oauth) = &resolved.auth {
+            let token = match (self.tokens.cached(oauth), self.tokens.expires_in(oauth)) {
+                (Some(_), Some(expires_in)) => {
+                    format!("Token cached, expires in {}s", expires_in.as_secs())
+                }
+                (Some(_), None) => "Token cached".to_string(),
+                (None, _) => "A token is fetched when the request is sent".to_string(),
+            };
+            lines.push(Line::styled(token, Style::default().fg(Color::DarkGray)));
+        }
+        if !resolved.no_cookies
+            && let Some(cookies) = self.cookies.header(&parse_into_https(&resolved.url))
+        {
+            lines.push(Line::from(vec![
+                Span::styled("Cookie: ", Style::default().fg(Color::LightCyan)),
+                Span::raw(cookies),
+                Span::styled(
+                    " (from the cookie jar)",
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+        lines.push(Line::raw(""));
+        lines.push(heading("Body"));
+        lines.extend(body_lines(&resolved));
+        if !unresolved.is_empty() {
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(
+                format!("Unresolved variables: {}", unresolved.join(", ")),
+                Style::default().fg(Color::LightRed),
+            ));
+        }
+        lines.push(Line::raw(""));
+        lines.push(heading("curl"));
+        let command = curl::to_masked_curl(&resolved, &parse_into_https(&resolved.url));
+        lines.extend(command.lines().map(|l| Line::raw(l.to_string())));
+
+        let title = match &self.active_environment {
+            Some(name) => format!("Resolved request - env: {}", name),
+            None => "Resolved request - no environment".to_string(),
+        };
+        let area = centered_rect(80, frame.area().height * 2 / 3, frame.area());
+        let preview = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_style(
+                    Style::default()
+                        .fg(Color::LightYellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::White)),
         );
-        frame.render_widget(request_body_block, body_horizontal_chunks[0]);
+        frame.render_widget(Clear, area);
+        frame.render_widget(preview, area);
+    }
 
-        let headers_block =
-            generate_paragraph(&self.headers, "Headers".to_string(), self.active_block == 3);
-        frame.render_widget(headers_block, body_horizontal_chunks[1]);
+    fn render_environments_tab(&mut self, frame: &mut Frame, area: Rect) {
+        let [environments_area, settings_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(area);
+        let [variables_area, tls_area, proxy_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(tls::FIELDS.len() as u16 + 2),
+            Constraint::Length(proxy::FIELDS.len() as u16 + 2),
+        ])
+        .areas(settings_area);
+        let pane = self.environment_pane;
+        let focused_border = |focused: bool| {
+            if focused {
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::LightBlue)
+            }
+        };
 
-        let response_body_block = generate_paragraph(
-            &self.response,
-            "Response".to_string(),
-            self.active_block == 4,
+        let items: Vec<ListItem> = self
+            .environments
+            .iter()
+            .map(|environment| {
+                if self.active_environment.as_ref() == Some(&environment.name) {
+                    ListItem::new(Line::styled(
+                        format!("● {}", environment.name),
+                        Style::default().fg(Color::Green),
+                    ))
+                } else {
+                    ListItem::new(format!("  {}", environment.name))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Environments")
+                    .title_style(
+                        Style::default()
+                            .fg(Color::LightYellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .border_style(focused_border(pane == EnvironmentPane::Environments)),
+            )
+            .highlight_style(Style::default().fg(Color::Yellow).bg(Color::DarkGray));
+        frame.render_stateful_widget(list, environments_area, &mut self.environments_state);
+
+        let (title, items): (String, Vec<ListItem>) = match self.selected_environment() {
+            Some(environment) => (
+                format!("Variables - {}", environment.name),
+                environment
+                    .variables
+                    .iter()
+                    .map(|(name, value)| {
+                        ListItem::new(Line::from(vec![
+                            Span::styled(
+                                format!("{} = ", name),
+                                Style::default().fg(Color::LightCyan),
+                            ),
+                            Span::raw(value.clone()),
+                        ]))
+                    })
+                    .collect(),
+            ),
+            None => (
+                "Variables".to_string(),
+                vec![ListItem::new("Press [N] to create an environment")],
+            ),
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(
+                        Style::default()
+                            .fg(Color::LightYellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .border_style(focused_border(pane == EnvironmentPane::Variables)),
+            )
+            .highlight_style(if pane == EnvironmentPane::Variables {
+                Style::default().fg(Color::Yellow).bg(Color::DarkGray)
+            } else {
+                Style::default()
+            });
+        frame.render_stateful_widget(list, variables_area, &mut self.variables_state);
+
+        let tls = self
+            .selected_environment()
+            .map(|environment| environment.tls.clone())
+            .unwrap_or_default();
+        let items: Vec<ListItem> = tls::FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<20}", label),
+                        Style::default().fg(Color::LightCyan),
+                    ),
+                    Span::raw(tls.value(i)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("TLS")
+                    .title_style(
+                        Style::default()
+                            .fg(Color::LightYellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .border_style(focused_border(pane == EnvironmentPane::Tls)),
+            )
+            .highlight_style(if pane == EnvironmentPane::Tls {
+                Style::default().fg(Color::Yellow).bg(Color::DarkGray)
+            } else {
+                Style::default()
+            });
+        frame.render_stateful_widget(list, tls_area, &mut self.tls_state);
+
+        let (title, proxy) = match self.selected_environment() {
+            Some(environment) if !self.proxy_global => {
+                if environment.proxy.is_default() {
+                    (
+                        format!(
+                            "Proxy - {} (none set, the global settings apply)",
+                            environment.name
+                        ),
+                        environment.proxy.clone(),
+                    )
+                } else {
+                    (
+                        format!("Proxy - {}", environment.name),
+                        environment.proxy.clone(),
+                    )
+                }
+            }
+            _ => ("Proxy - global".to_string(), self.proxy.clone()),
+        };
+        let items: Vec<ListItem> = proxy::FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<20}", label),
+                        Style::default().fg(Color::LightCyan),
+                    ),
+                    Span::raw(proxy.value(i)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(
+                        Style::default()
+                            .fg(Color::LightYellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .border_style(focused_border(pane == EnvironmentPane::Proxy)),
+            )
+            .highlight_style(if pane == EnvironmentPane::Proxy {
+                Style::default().fg(Color::Yellow).bg(Color::DarkGray)
+            } else {
+                Style::default()
+            });
+        frame.render_stateful_widget(list, proxy_area, &mut self.proxy_state);
+    }
+
+    fn render_cookies_tab(&mut self, frame: &mut Frame, area: Rect) {
+        let cookies = self.cookies.cookies();
+        let selected = self.cookies_state.selected().unwrap_or(0);
+        self.cookies_state
+            .select(Some(cmp::min(selected, cookies.len().saturating_sub(1))));
+
+        let mut title = match &self.active_environment {
+            Some(name) => format!("Cookies - env: {}", name),
+            None => "Cookies - no environment".to_string(),
+        };
+        let enabled = self.cookies.is_enabled();
+        title.push_str(if enabled { " - on" } else { " - off" });
+
+        let items: Vec<ListItem> = if cookies.is_empty() {
+            vec![ListItem::new(if enabled {
+                "No cookies yet, they're stored as responses set them. Press [n] to add one"
+            } else {
+                "The cookie jar is off. Press [c] to send and store cookies"
+            })]
+        } else {
+            cookies
+                .iter()
+                .map(|cookie| {
+                    ListItem::new(Line::from(vec![
+                        Span::styled(
+                            format!("{}{} ", cookie.domain, cookie.path),
+                            Style::default().fg(Color::LightCyan),
+                        ),
+                        Span::raw(format!("{}={} ", cookie.name, cookie.value)),
+                        Span::styled(cookie.details(), Style::default().fg(Color::DarkGray)),
+                    ]))
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(
+                        Style::default()
+                            .fg(Color::LightYellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .border_style(
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+            )
+            .highlight_style(Style::default().fg(Color::Yellow).bg(Color::DarkGray));
+        frame.render_stateful_widget(list, area, &mut self.cookies_state);
+    }
+
+    fn render_collections_tab(&mut self, frame: &mut Frame, area: Rect) {
+        let [tree_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(area);
+
+        let items: Vec<ListItem> = self
+            .collection_nodes
+            .iter()
+            .map(|node| {
+                let indent = "  ".repeat(node.depth);
+                let line = match node.kind {
+                    NodeKind::Folder => {
+                        let marker = if self.collections_expanded.contains(&node.path) {
+                            "▾"
+                        } else {
+                            "▸"
+                        };
+                        Line::styled(
+                            format!("{}{} {}", indent, marker, node.name),
+                            Style::default()
+                                .fg(Color::LightCyan)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    }
+                    NodeKind::Request => {
+                        let style = if self.opened_request.as_ref() == Some(&node.path) {
+                            Style::default().fg(Color::Green)
+                        } else {
+                            Style::default().fg(Color::White)
+                        };
+                        Line::styled(format!("{}  {}", indent, node.name), style)
+                    }
+                    NodeKind::HttpFile => {
+                        let marker = if self.collections_expanded.contains(&node.path) {
+                            "▾"
+                        } else {
+                            "▸"
+                        };
+                        Line::styled(
+                            format!("{}{} {}", indent, marker, node.name),
+                            Style::default().fg(Color::LightMagenta),
+                        )
+                    }
+                    NodeKind::HttpRequest(index) => {
+                        let opened =
+                            self.opened_http_request.as_ref() == Some(&(node.path.clone(), index));
+                        let style = if opened {
+                            Style::default().fg(Color::Green)
+                        } else {
+                            Style::default().fg(Color::White)
+                        };
+                        Line::styled(format!("{}  {}", indent, node.name), style)
+                    }
+                };
+                ListItem::new(line)
+            })
+            .collect();
+        let title = format!("Collections - {}", collections::collections_dir().display());
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(
+                        Style::default()
+                            .fg(Color::LightYellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .border_style(Style::default().fg(Color::LightBlue)),
+            )
+            .highlight_style(Style::default().fg(Color::Yellow).bg(Color::DarkGray));
+        frame.render_stateful_widget(list, tree_area, &mut self.collections_state);
+
+        let selected = self
+            .collections_state
+            .selected()
+            .and_then(|i| self.collection_nodes.get(i));
+        let request_preview = |request: SavedRequest| {
+            let mut lines = vec![Line::styled(
+                format!("{} {}", request.method, request.url),
+                Style::default()
+                    .fg(Color::LightCyan)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            lines.extend(header_lines(&request.headers));
+            lines.extend(auth_line(&request));
+            lines.extend(body_lines(&request));
+            Text::from(lines)
+        };
+        let error_preview =
+            |e: color_eyre::Report| Text::styled(e.to_string(), Style::default().fg(Color::Red));
+        let preview = match selected.map(|node| (node, node.kind)) {
+            Some((node, NodeKind::Request)) => match collections::load_request(&node.path) {
+                Ok(request) => request_preview(request),
+                Err(e) => error_preview(e),
+            },
+            Some((node, NodeKind::HttpRequest(index))) => match http_file::load(&node.path) {
+                Ok(mut file) if index < file.requests.len() => {
+                    let request = file.requests.swap_remove(index);
+                    match request.unsupported {
+                        Some(reason) => error_preview(eyre!("Can't open this request: {}", reason)),
+                        None => request_preview(request.request),
+                    }
+                }
+                Ok(_) => Text::raw("This request is no longer in the file"),
+                Err(e) => error_preview(e),
+            },
+            Some((node, NodeKind::HttpFile)) => match http_file::load(&node.path) {
+                Ok(file) => {
+                    let mut lines = vec![
+                        Line::styled(
+                            node.path.display().to_string(),
+                            Style::default()
+                                .fg(Color::LightCyan)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Line::raw(format!("{} requests", file.requests.len())),
+                    ];
+                    lines.extend(
+                        file.variables
+                            .iter()
+                            .map(|(name, value)| Line::raw(format!("@{} = {}", name, value))),
+                    );
+                    Text::from(lines)
+                }
+                Err(e) => error_preview(e),
+            },
+            Some(_) => Text::raw("[enter] Expand or collapse this folder"),
+            None => {
+                Text::raw("No saved requests yet. Press [s] in the Request/Reply tab to save one.")
+            }
+        };
+        let preview = Paragraph::new(preview).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Preview")
+                .title_style(
+                    Style::default()
+                        .fg(Color::LightYellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::LightBlue)),
+        );
+        frame.render_widget(preview, preview_area);
+    }
+
+    fn render_history_tab(&mut self, frame: &mut Frame, area: Rect) {
+        let [filter_area, body_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+        let [list_area, details_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body_area);
+
+        let filter = generate_paragraph(
+            &self.history_filter,
+            "Filter (method, URL or status)".to_string(),
+            self.history_filter.edit_mode,
+        );
+        frame.render_widget(filter, filter_area);
+
+        let items: Vec<ListItem> = self
+            .filtered_history()
+            .into_iter()
+            .map(|i| {
+                let entry = &self.history[i];
+                let status_style = match entry.response.status {
+                    Some(status) => Style::default().fg(reqwest::StatusCode::from_u16(status)
+                        .map(status_color)
+                        .unwrap_or(Color::White)),
+                    None => Style::default().fg(Color::Red),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        entry.sent_at.format("%Y-%m-%d %H:%M:%S  ").to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("{:<7}", entry.request.method),
+                        Style::default().fg(Color::LightCyan),
+                    ),
+                    Span::styled(format!("{:<4}", entry.response.status_text()), status_style),
+                    Span::styled(
+                        format!("{:>7} ms  ", entry.response.elapsed_ms),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(entry.request.url.clone()),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("History ({})", self.history.len()))
+                    .title_style(
+                        Style::default()
+                            .fg(Color::LightYellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .border_style(Style::default().fg(Color::LightBlue)),
+            )
+            .highlight_style(Style::default().fg(Color::Yellow).bg(Color::DarkGray));
+        frame.render_stateful_widget(list, list_area, &mut self.history_state);
+
+        let details = match self.selected_history_entry() {
+            Some(entry) => {
+                let heading = |label: &str| {
+                    Line::styled(
+                        label.to_string(),
+                        Style::default()
+                            .fg(Color::LightCyan)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                };
+                let mut lines = vec![
+                    heading("Request"),
+                    Line::raw(format!("{} {}", entry.request.method, entry.request.url)),
+                ];
+                lines.extend(header_lines(&entry.request.headers));
+                lines.extend(auth_line(&entry.request));
+                lines.extend(body_lines(&entry.request));
+                lines.push(Line::raw(""));
+                lines.push(heading("Response"));
+                match &entry.response.error {
+                    Some(error) => {
+                        lines.push(Line::styled(error.clone(), Style::default().fg(Color::Red)))
+                    }
+                    None => {
+                        lines.push(Line::raw(format!(
+                            "{} {} · {} ms · {}",
+                            entry.response.http_version,
+                            entry.response.status_text(),
+                            entry.response.elapsed_ms,
+                            format_size(entry.response.size)
+                        )));
+                        lines.extend(
+                            entry
+                                .response
+                                .headers
+                                .iter()
+                                .map(|(name, value)| Line::raw(format!("{}: {}", name, value))),
+                        );
+                    }
+                }
+                Text::from(lines)
+            }
+            None => Text::raw("No requests sent yet"),
+        };
+        let details = Paragraph::new(details).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Details")
+                .title_style(
+                    Style::default()
+                        .fg(Color::LightYellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::LightBlue)),
         );
-        frame.render_widget(response_body_block, chunks[2]);
+        frame.render_widget(details, details_area);
     }
 
     fn render_load_test_tab(&mut self, frame: &mut Frame, area: Rect) {
@@ -649,7 +3043,11 @@ impl App {
             ])
             .split(area);
 
-        let url = generate_paragraph(&self.load_test_url, "Load test url".to_string(), true);
+        let mut url_title = "Load test url".to_string();
+        if !self.auth.auth.is_none() {
+            url_title.push_str(&format!(" - auth: {}", self.auth.auth.label()));
+        }
+        let url = generate_paragraph(&self.load_test_url, url_title, true);
         let load_test_result_clone = self.load_test_result.clone();
         let load_test_result_clone_lock = load_test_result_clone.lock().unwrap();
         let result = Paragraph::new(load_test_result_clone_lock.value.to_string())
@@ -671,7 +3069,14 @@ impl App {
         frame.render_widget(result, vertical_chunks[2]);
     }
 
-    fn run_load_test(url: String, result: Arc<Mutex<DisplayString>>) {
+    fn run_load_test(
+        url: String,
+        auth: Auth,
+        tls: TlsConfig,
+        proxy: ProxySettings,
+        tokens: TokenCache,
+        result: Arc<Mutex<DisplayString>>,
+    ) {
         std::thread::spawn(move || {
             let runtime = Runtime::new().unwrap();
             let mut tps = 10;
@@ -679,7 +3084,45 @@ impl App {
             result_lock.append_string("Running load test...".to_string());
             drop(result_lock);
 
+            let mut url = url;
+            let mut headers = HeaderMap::new();
+            if let Err(e) = auth.apply(&mut url, &mut headers) {
+                let mut result_lock = result.lock().unwrap();
+                result_lock.append_string(format!("Invalid auth: {}", e));
+                return;
+            }
+
+            let client = match proxy
+                .apply(tls.apply(Client::builder()))
+                .and_then(|builder| Ok(builder.build()?))
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    let mut result_lock = result.lock().unwrap();
+                    result_lock.append_string(format!("Invalid TLS or proxy settings: {}", e));
+                    return;
+                }
+            };
+
             loop {
+                // Each request is authorized as it's sent, this catches a
+                // token endpoint or signing failure once instead of failing
+                // every request of the round.
+                let mut probe = HttpRequest {
+                    request_type: RequestType::GET,
+                    url: url.clone(),
+                    headers: headers.clone(),
+                    body: None,
+                    cookies: None,
+                    redirects: Redirects::default(),
+                    added_auth: AddedAuth::default(),
+                };
+                if let Err(e) = runtime.block_on(auth.authorize(&client, &tokens, &mut probe)) {
+                    let mut result_lock = result.lock().unwrap();
+                    result_lock.append_string(format!("{:#}", e));
+                    break;
+                }
+
                 let success_count = Arc::new(AtomicUsize::new(0));
                 let failure_count = Arc::new(AtomicUsize::new(0));
                 let duration = Duration::from_secs(10);
@@ -691,21 +3134,50 @@ impl App {
 
                     while Instant::now() - start_time < duration {
                         for _ in 0..tps {
-                            let client_clone = Arc::new(reqwest::Client::new()); // Need a non-blocking client
+                            // Each request gets a client of its own, with
+                            // the environment's TLS and proxy settings.
+                            let client_clone = proxy
+                                .apply(tls.apply(Client::builder()))
+                                .and_then(|builder| Ok(builder.build()?));
                             let endpoint_clone = url.clone();
+                            let headers_clone = headers.clone();
+                            let auth_clone = auth.clone();
+                            let tokens_clone = tokens.clone();
                             let success_count_clone = success_count.clone();
                             let failure_count_clone = failure_count.clone();
 
                             tasks.push(tokio::spawn(async move {
-                                let result = client_clone.get(endpoint_clone).send().await;
+                                // OAuth2 tokens come from the cache and are
+                                // renewed when they expire, AWS signatures
+                                // are made per request.
+                                let mut request = HttpRequest {
+                                    request_type: RequestType::GET,
+                                    url: endpoint_clone,
+                                    headers: headers_clone,
+                                    body: None,
+                                    cookies: None,
+                                    redirects: Redirects::default(),
+                                    added_auth: AddedAuth::default(),
+                                };
+                                let result = match client_clone {
+                                    Ok(client) => match auth_clone
+                                        .authorize(&client, &tokens_clone, &mut request)
+                                        .await
+                                    {
+                                        Ok(()) => client
+                                            .get(request.url)
+                                            .headers(request.headers)
+                                            .send()
+                                            .await
+                                            .ok(),
+                                        Err(_) => None,
+                                    },
+                                    Err(_) => None,
+                                };
 
                                 match result {
-                                    Ok(response) => {
-                                        if response.status().is_success() {
-                                            success_count_clone.fetch_add(1, Ordering::SeqCst);
-                                        } else {
-                                            failure_count_clone.fetch_add(1, Ordering::SeqCst);
-                                        }
+                                    Some(response) if response.status().is_success() => {
+                                        success_count_clone.fetch_add(1, Ordering::SeqCst);
                                     }
                                     _ => {
                                         failure_count_clone.fetch_add(1, Ordering::SeqCst);
@@ -747,11 +3219,284 @@ impl App {
     }
 }
 
+fn response_lines<'a>(
+    response: &HttpResponse,
+    view: ResponseView,
+    body_lines: &'a [Line<'static>],
+) -> Cow<'a, [Line<'static>]> {
+    let header_line = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(
+                format!("{}: ", name),
+                Style::default()
+                    .fg(Color::LightCyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ])
+    };
+    match view {
+        ResponseView::Body => Cow::Borrowed(body_lines),
+        ResponseView::Headers => Cow::Owned(
+            response
+                .headers
+                .iter()
+                .map(|(name, value)| header_line(name, value.clone()))
+                .collect(),
+        ),
+        ResponseView::Exchange => {
+            let mut lines = Vec::new();
+            for (index, exchange) in response.exchanges.iter().enumerate() {
+                if index > 0 {
+                    lines.push(Line::raw(""));
+                }
+                lines.push(Line::styled(
+                    format!("→ {} {}", exchange.method, exchange.url),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+                lines.extend(
+                    exchange
+                        .request_headers
+                        .iter()
+                        .map(|(name, value)| header_line(name, value.clone())),
+                );
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("← {}", exchange.status),
+                        Style::default()
+                            .fg(status_color(exchange.status))
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" {} ms", exchange.elapsed.as_millis()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+                lines.extend(
+                    exchange
+                        .headers
+                        .iter()
+                        .map(|(name, value)| header_line(name, value.clone())),
+                );
+            }
+            Cow::Owned(lines)
+        }
+        ResponseView::Tls if response.tls.is_none() && response.url.starts_with("https:") => {
+            Cow::Owned(vec![Line::raw("Reading the TLS details…")])
+        }
+        ResponseView::Tls => Cow::Owned(tls_lines(response.tls.as_ref(), header_line)),
+        ResponseView::Info => {
+            let info_line = |label: &str, value: Span<'static>| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<10}", label),
+                        Style::default()
+                            .fg(Color::LightCyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    value,
+                ])
+            };
+            Cow::Owned(vec![
+                info_line(
+                    "Status",
+                    Span::styled(
+                        response.status_line(),
+                        Style::default().fg(status_color(response.status)),
+                    ),
+                ),
+                info_line(
+                    "Time",
+                    Span::raw(format!(
+                        "{} ms ({} ms waiting for the first byte)",
+                        response.elapsed.as_millis(),
+                        response.wait.as_millis()
+                    )),
+                ),
+                info_line(
+                    "Size",
+                    Span::raw(format!(
+                        "{} ({} bytes)",
+                        format_size(response.size()),
+                        response.size()
+                    )),
+                ),
+                info_line("URL", Span::raw(response.url.clone())),
+                info_line(
+                    "Redirects",
+                    Span::raw(response.redirects_followed().to_string()),
+                ),
+            ])
+        }
+    }
+}
+
+/// The TLS view, the handshake then each certificate the server sent.
+fn tls_lines(
+    tls: Option<&Result<TlsDetails, String>>,
+    header_line: impl Fn(&str, String) -> Line<'static>,
+) -> Vec<Line<'static>> {
+    let tls = match tls {
+        None => return vec![Line::raw("Not an HTTPS response")],
+        Some(Err(e)) => {
+            return vec![Line::styled(
+                format!("Could not read the TLS details: {}", e),
+                Style::default().fg(Color::Red),
+            )];
+        }
+        Some(Ok(tls)) => tls,
+    };
+
+    let mut lines = vec![
+        header_line("Version", tls.version.clone()),
+        header_line("Cipher", tls.cipher.clone()),
+        header_line(
+            "ALPN",
+            tls.alpn.clone().unwrap_or_else(|| "none".to_string()),
+        ),
+        match &tls.verify_error {
+            None => header_line("Verified", "yes".to_string()),
+            Some(e) => Line::from(vec![
+                Span::styled(
+                    "Verified: ",
+                    Style::default()
+                        .fg(Color::LightCyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("no, {}", e), Style::default().fg(Color::Red)),
+            ]),
+        },
+    ];
+    if let Some(warning) = tls.expiry_warning() {
+        lines.push(Line::styled(
+            format!("⚠ {}", warning),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    for (index, certificate) in tls.chain.iter().enumerate() {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            if index == 0 {
+                "Server certificate".to_string()
+            } else {
+                format!("Chain certificate {}", index)
+            },
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        lines.push(header_line("Subject", certificate.subject.clone()));
+        if !certificate.sans.is_empty() {
+            lines.push(header_line("SANs", certificate.sans.join(", ")));
+        }
+        lines.push(header_line("Issuer", certificate.issuer.clone()));
+        lines.push(header_line(
+            "Valid from",
+            certificate
+                .not_before
+                .format("%Y-%m-%d %H:%M UTC")
+                .to_string(),
+        ));
+        let days_left = (certificate.not_after - chrono::Utc::now()).num_days();
+        let (left, color) = if certificate.not_after <= chrono::Utc::now() {
+            ("expired".to_string(), Color::Red)
+        } else if days_left < tls_info::EXPIRY_WARNING_DAYS {
+            (format!("{} days left", days_left), Color::Yellow)
+        } else {
+            (format!("{} days left", days_left), Color::Green)
+        };
+        let mut expires = header_line(
+            "Expires",
+            format!("{} ", certificate.not_after.format("%Y-%m-%d %H:%M UTC")),
+        );
+        expires.push_span(Span::styled(
+            format!("({})", left),
+            Style::default().fg(color),
+        ));
+        lines.push(expires);
+    }
+
+    lines
+}
+
+/// Headers as `Name: value` lines, with disabled ones dimmed.
+fn header_lines(headers: &[KeyValue]) -> Vec<Line<'static>> {
+    headers
+        .iter()
+        .filter(|header| !header.name.trim().is_empty())
+        .map(|header| {
+            let line = format!("{}: {}", header.name.trim(), header.value);
+            if header.enabled {
+                Line::raw(line)
+            } else {
+                Line::styled(line, Style::default().fg(Color::DarkGray))
+            }
+        })
+        .collect()
+}
+
+/// The auth added at send time, with its secrets masked.
+fn auth_line(request: &SavedRequest) -> Option<Line<'static>> {
+    request
+        .auth
+        .summary()
+        .map(|summary| Line::styled(summary, Style::default().fg(Color::LightYellow)))
+}
+
+/// The body as it will be sent: the raw text, the encoded form or one line
+/// per multipart field.
+fn body_lines(request: &SavedRequest) -> Vec<Line<'static>> {
+    match request.body_mode {
+        BodyMode::Raw => request
+            .body
+            .lines()
+            .map(|l| Line::raw(l.to_string()))
+            .collect(),
+        BodyMode::FormUrlencoded if enabled_fields(&request.form).next().is_none() => Vec::new(),
+        BodyMode::FormUrlencoded => vec![Line::raw(form_body(&request.form))],
+        BodyMode::File => vec![Line::raw(format!("file {}", request.body_file))],
+        BodyMode::Multipart => enabled_fields(&request.form)
+            .map(|field| {
+                if field.file {
+                    Line::raw(format!("{}: file {}", field.name.trim(), field.value))
+                } else {
+                    Line::raw(format!("{}: {}", field.name.trim(), field.value))
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Copies text to the system clipboard with the OSC 52 escape sequence, which
+/// most terminals support, including over SSH.
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
+
+fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let [_, vertical, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(height),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    let [_, horizontal, _] = Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .areas(vertical);
+    horizontal
+}
+
 fn generate_paragraph(
     display_string: &DisplayString,
     title: String,
     chunk_active: bool,
-) -> Paragraph {
+) -> Paragraph<'_> {
     let mut display_value = display_string.value.to_string();
     let mut display_title = title;
 
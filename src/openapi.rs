@@ -0,0 +1,496 @@
+use crate::{
+    auth::{ApiKeyLocation, Auth},
+    collections::{self, collections_dir},
+    http::{BodyMode, KeyValue, SavedRequest},
+    import::{ImportSummary, save_variables},
+    oauth::{Grant, OAuth2},
+};
+use color_eyre::{Result, eyre::eyre};
+use serde_json::{Map, Value, json};
+use std::{collections::BTreeMap, fs};
+
+static METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How many `$ref` hops to follow before giving up on a reference chain.
+static MAX_REF_HOPS: usize = 8;
+
+/// Imports an OpenAPI 3 spec as a new collection with one request per
+/// operation, grouped in folders by their first tag. Requests use
+/// `{{baseUrl}}` and `{{param}}` placeholders, the values the spec provides
+/// for them go into an environment named after the collection.
+pub fn import_spec(spec: Value) -> Result<ImportSummary> {
+    let version = spec["openapi"].as_str().unwrap_or_default();
+    if !version.starts_with('3') {
+        return Err(eyre!("Unsupported OpenAPI version: {}", spec["openapi"]));
+    }
+    let paths = spec["paths"]
+        .as_object()
+        .ok_or_else(|| eyre!("The spec has no paths"))?;
+
+    let title = spec["info"]["title"].as_str().unwrap_or("OpenAPI");
+    let dir = collections::unique_folder_path(&collections_dir(), title);
+    fs::create_dir_all(&dir)?;
+    let mut summary = ImportSummary {
+        collection: collections::request_location(&dir),
+        requests: 0,
+        skipped: Vec::new(),
+        stripped: Vec::new(),
+        environment: None,
+    };
+
+    let mut variables = BTreeMap::new();
+    variables.insert("baseUrl".to_string(), base_url(&spec));
+
+    for (path, path_item) in paths {
+        let path_item = resolve(&spec, path_item);
+        for method in METHODS {
+            let Some(operation) = path_item.get(method) else {
+                continue;
+            };
+            let request = build_request(&spec, method, path, path_item, operation, &mut variables);
+            let name = operation["operationId"]
+                .as_str()
+                .or(operation["summary"].as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+            let folder = match operation["tags"][0].as_str() {
+                Some(tag) => dir.join(collections::sanitize(tag)),
+                None => dir.clone(),
+            };
+            let file = collections::unique_request_path(&folder, &name);
+            match collections::save_request(&file, &request) {
+                Ok(stripped) => {
+                    summary.record_stripped(&name, stripped);
+                    summary.requests += 1;
+                }
+                Err(e) => summary.skipped.push(format!("{}: {}", name, e)),
+            }
+        }
+    }
+    summary.environment = Some(save_variables(&summary.collection, variables)?);
+
+    Ok(summary)
+}
+
+/// The first server's URL with its variables filled in from their defaults.
+fn base_url(spec: &Value) -> String {
+    let server = &spec["servers"][0];
+    let mut url = server["url"].as_str().unwrap_or("").to_string();
+    if let Some(server_variables) = server["variables"].as_object() {
+        for (name, variable) in server_variables {
+            let default = variable["default"].as_str().unwrap_or_default();
+            url = url.replace(&format!("{{{}}}", name), default);
+        }
+    }
+
+    url.trim_end_matches('/').to_string()
+}
+
+/// Follows a local `$ref` like `#/components/schemas/Pet`. Anything else is
+/// returned as is.
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+    for _ in 0..MAX_REF_HOPS {
+        let Some(reference) = value["$ref"].as_str() else {
+            break;
+        };
+        let Some(pointer) = reference.strip_prefix('#') else {
+            break;
+        };
+        let pointer = pointer.replace("~1", "/").replace("~0", "~");
+        match spec.pointer(&pointer) {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+
+    value
+}
+
+fn build_request(
+    spec: &Value,
+    method: &str,
+    path: &str,
+    path_item: &Value,
+    operation: &Value,
+    variables: &mut BTreeMap<String, String>,
+) -> SavedRequest {
+    let mut url = format!(
+        "{{{{baseUrl}}}}{}",
+        path.replace('{', "{{").replace('}', "}}")
+    );
+    let mut query = Vec::new();
+    let mut headers = BTreeMap::new();
+
+    // Operation parameters override path level ones with the same name and
+    // location.
+    let mut parameters: Vec<&Value> = Vec::new();
+    for parameter in operation["parameters"]
+        .as_array()
+        .into_iter()
+        .chain(path_item["parameters"].as_array())
+        .flatten()
+    {
+        let parameter = resolve(spec, parameter);
+        if !parameters
+            .iter()
+            .any(|p| p["name"] == parameter["name"] && p["in"] == parameter["in"])
+        {
+            parameters.push(parameter);
+        }
+    }
+
+    for parameter in parameters {
+        let Some(name) = parameter["name"].as_str() else {
+            continue;
+        };
+        let required = parameter["required"].as_bool().unwrap_or(false);
+        let value = parameter_value(spec, parameter);
+        match parameter["in"].as_str() {
+            Some("path") => {
+                if let Some(value) = value {
+                    variables.entry(name.to_string()).or_insert(value);
+                }
+            }
+            Some("query") if required => {
+                query.push(format!("{}={{{{{}}}}}", name, name));
+                if let Some(value) = value {
+                    variables.entry(name.to_string()).or_insert(value);
+                }
+            }
+            Some("header") if required => {
+                headers.insert(
+                    name.to_string(),
+                    value.unwrap_or_else(|| format!("{{{{{}}}}}", name)),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let mut body = String::new();
+    let mut body_mode = BodyMode::Raw;
+    let mut form = Vec::new();
+    let request_body = resolve(spec, &operation["requestBody"]);
+    if let Some(content) = request_body["content"].as_object()
+        && let Some((content_type, media)) = content
+            .iter()
+            .find(|(content_type, _)| content_type.contains("json"))
+            .or_else(|| content.iter().next())
+    {
+        let example = media_example(spec, media);
+        if content_type == "application/x-www-form-urlencoded" {
+            body_mode = BodyMode::FormUrlencoded;
+            form = form_fields(spec, &media["schema"], &example);
+        } else if content_type == "multipart/form-data" {
+            body_mode = BodyMode::Multipart;
+            form = form_fields(spec, &media["schema"], &example);
+        } else if resolve(spec, &media["schema"])["format"] == "binary" {
+            // Uploads, the user picks the file to send.
+            body_mode = BodyMode::File;
+            headers.insert("Content-Type".to_string(), content_type.clone());
+        } else {
+            headers.insert("Content-Type".to_string(), content_type.clone());
+        }
+        body = if body_mode != BodyMode::Raw {
+            String::new()
+        } else if content_type.contains("json") {
+            serde_json::to_string_pretty(&example).unwrap_or_default()
+        } else {
+            match example {
+                Value::Null => String::new(),
+                Value::String(text) => text,
+                example => example.to_string(),
+            }
+        };
+    }
+
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+
+    SavedRequest {
+        method: method.to_uppercase(),
+        url,
+        headers: headers
+            .into_iter()
+            .map(|(name, value)| KeyValue::new(name, value))
+            .collect(),
+        body,
+        body_mode,
+        form,
+        auth: security_auth(spec, operation),
+        ..Default::default()
+    }
+}
+
+/// An example or default value for a parameter, if the spec has one.
+fn parameter_value(spec: &Value, parameter: &Value) -> Option<String> {
+    let schema = resolve(spec, &parameter["schema"]);
+    let value = [
+        &parameter["example"],
+        &schema["example"],
+        &schema["default"],
+        &schema["enum"][0],
+    ]
+    .into_iter()
+    .find(|value| !value.is_null())?;
+
+    Some(match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    })
+}
+
+/// The auth for the first security requirement of the operation, falling
+/// back to the spec wide one. The credentials are left as `{{...}}`
+/// placeholders to fill in from an environment.
+fn security_auth(spec: &Value, operation: &Value) -> Auth {
+    let requirements = if operation.get("security").is_some() {
+        &operation["security"]
+    } else {
+        &spec["security"]
+    };
+    let Some(requirement) = requirements[0].as_object() else {
+        return Auth::None;
+    };
+
+    for scheme_name in requirement.keys() {
+        let scheme = resolve(spec, &spec["components"]["securitySchemes"][scheme_name]);
+        // API Gateway exports describe IAM auth as an API key scheme.
+        if scheme["x-amazon-apigateway-authtype"]
+            .as_str()
+            .is_some_and(|auth_type| auth_type.eq_ignore_ascii_case("awsSigv4"))
+        {
+            return Auth::AwsSigV4 {
+                access_key: "{{aws_access_key_id}}".to_string(),
+                secret_key: "{{aws_secret_access_key}}".to_string(),
+                session_token: String::new(),
+                region: "{{aws_region}}".to_string(),
+                service: "execute-api".to_string(),
+            };
+        }
+        match (scheme["type"].as_str(), scheme["in"].as_str()) {
+            (Some("apiKey"), Some(location @ ("header" | "query"))) => {
+                if let Some(name) = scheme["name"].as_str() {
+                    return Auth::ApiKey {
+                        name: name.to_string(),
+                        value: format!("{{{{{}}}}}", scheme_name),
+                        location: if location == "query" {
+                            ApiKeyLocation::Query
+                        } else {
+                            ApiKeyLocation::Header
+                        },
+                    };
+                }
+            }
+            (Some("http"), _) => match scheme["scheme"].as_str() {
+                Some(s) if s.eq_ignore_ascii_case("bearer") => {
+                    return Auth::Bearer {
+                        token: "{{token}}".to_string(),
+                    };
+                }
+                Some(s) if s.eq_ignore_ascii_case("basic") => {
+                    return Auth::Basic {
+                        username: "{{username}}".to_string(),
+                        password: "{{password}}".to_string(),
+                    };
+                }
+                Some(s) if s.eq_ignore_ascii_case("digest") => {
+                    return Auth::Digest {
+                        username: "{{username}}".to_string(),
+                        password: "{{password}}".to_string(),
+                    };
+                }
+                _ => {}
+            },
+            (Some("oauth2"), _) => {
+                return oauth2_auth(&scheme["flows"], &requirement[scheme_name]);
+            }
+            (Some("openIdConnect"), _) => {
+                return Auth::Bearer {
+                    token: "{{token}}".to_string(),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Auth::None
+}
+
+/// The client credentials or password flow when the scheme has one, those
+/// need no browser. Other flows are left to a `{{token}}` Bearer token.
+/// `scopes` are the ones the operation requires.
+fn oauth2_auth(flows: &Value, scopes: &Value) -> Auth {
+    let (grant, flow) = if flows["clientCredentials"].is_object() {
+        (Grant::ClientCredentials, &flows["clientCredentials"])
+    } else if flows["password"].is_object() {
+        (Grant::Password, &flows["password"])
+    } else {
+        return Auth::Bearer {
+            token: "{{token}}".to_string(),
+        };
+    };
+    let scope = scopes
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Auth::OAuth2(OAuth2 {
+        grant,
+        token_url: flow["tokenUrl"].as_str().unwrap_or_default().to_string(),
+        client_id: "{{client_id}}".to_string(),
+        client_secret: "{{client_secret}}".to_string(),
+        scope,
+        username: if grant == Grant::Password {
+            "{{username}}".to_string()
+        } else {
+            String::new()
+        },
+        password: if grant == Grant::Password {
+            "{{password}}".to_string()
+        } else {
+            String::new()
+        },
+        ..Default::default()
+    })
+}
+
+fn media_example(spec: &Value, media: &Value) -> Value {
+    if !media["example"].is_null() {
+        return media["example"].clone();
+    }
+    if let Some(examples) = media["examples"].as_object()
+        && let Some(example) = examples.values().next()
+    {
+        let example = resolve(spec, example);
+        if !example["value"].is_null() {
+            return example["value"].clone();
+        }
+    }
+
+    schema_example(spec, &media["schema"], &mut Vec::new()).unwrap_or(Value::Null)
+}
+
+/// Builds an example value from a schema, preferring the examples and
+/// defaults it declares and falling back to a placeholder for each type.
+/// `seen` holds the `$ref`s being expanded, a schema that refers back to one
+/// of them gives `None` and is left out rather than expanded forever.
+fn schema_example(spec: &Value, schema: &Value, seen: &mut Vec<String>) -> Option<Value> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        if seen.iter().any(|r| r == reference) {
+            return None;
+        }
+        seen.push(reference.to_string());
+        let example = schema_example(spec, resolve(spec, schema), seen);
+        seen.pop();
+        return example;
+    }
+
+    for key in ["example", "default", "const"] {
+        if !schema[key].is_null() {
+            return Some(schema[key].clone());
+        }
+    }
+    if let Some(first) = schema["enum"].as_array().and_then(|values| values.first()) {
+        return Some(first.clone());
+    }
+    if let Some(all_of) = schema["allOf"].as_array() {
+        let mut merged = Map::new();
+        for part in all_of {
+            if let Some(Value::Object(object)) = schema_example(spec, part, seen) {
+                merged.extend(object);
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(first) = schema[key].as_array().and_then(|values| values.first()) {
+            return schema_example(spec, first, seen);
+        }
+    }
+
+    let schema_type = match &schema["type"] {
+        // OpenAPI 3.1 allows a list of types, e.g. ["string", "null"].
+        Value::Array(types) => types
+            .iter()
+            .find_map(|t| t.as_str().filter(|t| *t != "null")),
+        schema_type => schema_type.as_str(),
+    };
+    let schema_type = schema_type.or_else(|| {
+        if schema.get("properties").is_some() {
+            Some("object")
+        } else if schema.get("items").is_some() {
+            Some("array")
+        } else {
+            None
+        }
+    });
+
+    let example = match schema_type? {
+        "object" => {
+            let mut object = Map::new();
+            if let Some(properties) = schema["properties"].as_object() {
+                for (name, property) in properties {
+                    if let Some(value) = schema_example(spec, property, seen) {
+                        object.insert(name.clone(), value);
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        "array" => match schema_example(spec, &schema["items"], seen) {
+            Some(item) => json!([item]),
+            None => json!([]),
+        },
+        "string" => json!(match schema["format"].as_str() {
+            Some("date-time") => "2024-01-01T00:00:00Z",
+            Some("date") => "2024-01-01",
+            Some("email") => "user@example.com",
+            Some("uuid") => "00000000-0000-0000-0000-000000000000",
+            Some("uri") | Some("url") => "https://example.com",
+            Some("ipv4") => "127.0.0.1",
+            _ => "string",
+        }),
+        "integer" => json!(0),
+        "number" => json!(0.0),
+        "boolean" => json!(true),
+        _ => return None,
+    };
+
+    Some(example)
+}
+
+/// One form field per property of the example. Binary properties become
+/// file fields for the user to pick a file for.
+fn form_fields(spec: &Value, schema: &Value, example: &Value) -> Vec<KeyValue> {
+    let Some(object) = example.as_object() else {
+        return Vec::new();
+    };
+    let properties = &resolve(spec, schema)["properties"];
+
+    object
+        .iter()
+        .map(|(name, value)| {
+            let property = resolve(spec, &properties[name]);
+            if property["format"] == "binary" {
+                return KeyValue {
+                    file: true,
+                    ..KeyValue::new(name, "")
+                };
+            }
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            KeyValue::new(name, value)
+        })
+        .collect()
+}
@@ -0,0 +1,456 @@
+use crate::{
+    auth::{ApiKeyLocation, Auth},
+    collections::{self, collections_dir},
+    http::{self, BodyMode, RequestType, SavedRequest, header_value},
+    import::{ImportSummary, save_variables},
+    oauth::{ClientAuth, Grant, OAuth2},
+};
+use color_eyre::{Result, eyre::eyre};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+// Just the parts of the Postman v2.1 collection format we import, everything
+// else in the file is ignored.
+
+#[derive(Deserialize)]
+struct Collection {
+    info: Info,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    variable: Vec<KeyValue>,
+    auth: Option<PostmanAuth>,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    name: String,
+    #[serde(default)]
+    schema: String,
+}
+
+/// Either a folder (has `item`) or a request (has `request`).
+#[derive(Deserialize)]
+struct Item {
+    #[serde(default)]
+    name: String,
+    item: Option<Vec<Item>>,
+    request: Option<Request>,
+    /// Folder auth, used by the requests in it that don't set their own.
+    auth: Option<PostmanAuth>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Request {
+    Url(String),
+    Full(Box<RequestDetails>),
+}
+
+#[derive(Deserialize)]
+struct RequestDetails {
+    #[serde(default = "default_method")]
+    method: String,
+    url: Option<Url>,
+    #[serde(default)]
+    header: Vec<KeyValue>,
+    body: Option<Body>,
+    auth: Option<PostmanAuth>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Url {
+    Raw(String),
+    Parts(Box<UrlParts>),
+}
+
+#[derive(Deserialize)]
+struct UrlParts {
+    raw: Option<String>,
+    protocol: Option<String>,
+    #[serde(default)]
+    host: Value,
+    port: Option<String>,
+    #[serde(default)]
+    path: Value,
+    #[serde(default)]
+    query: Vec<KeyValue>,
+}
+
+#[derive(Deserialize)]
+struct Body {
+    #[serde(default)]
+    mode: String,
+    #[serde(default)]
+    raw: String,
+    #[serde(default)]
+    urlencoded: Vec<KeyValue>,
+    #[serde(default)]
+    formdata: Vec<KeyValue>,
+    #[serde(default)]
+    file: Value,
+    options: Option<Value>,
+}
+
+/// Each auth type keeps its settings in a list under its own name.
+#[derive(Deserialize)]
+struct PostmanAuth {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    basic: Vec<KeyValue>,
+    #[serde(default)]
+    digest: Vec<KeyValue>,
+    #[serde(default)]
+    bearer: Vec<KeyValue>,
+    #[serde(default)]
+    apikey: Vec<KeyValue>,
+    #[serde(default)]
+    oauth2: Vec<KeyValue>,
+    #[serde(default)]
+    awsv4: Vec<KeyValue>,
+}
+
+#[derive(Deserialize)]
+struct KeyValue {
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    value: Value,
+    #[serde(default)]
+    disabled: bool,
+    /// `text` or `file` for form data fields.
+    #[serde(default, rename = "type")]
+    kind: String,
+    /// The path of a form data file, or a list of them.
+    #[serde(default)]
+    src: Value,
+}
+
+impl KeyValue {
+    fn value_text(&self) -> String {
+        match &self.value {
+            Value::Null => String::new(),
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
+fn enabled(values: &[KeyValue]) -> impl Iterator<Item = &KeyValue> {
+    values.iter().filter(|kv| !kv.disabled)
+}
+
+/// A form field as a row, disabled ones included. Only the first file of a
+/// multi-file field is kept.
+fn form_row(kv: &KeyValue) -> http::KeyValue {
+    let (value, file) = match (kv.kind.as_str(), &kv.src) {
+        ("file", Value::String(path)) => (path.clone(), true),
+        ("file", Value::Array(paths)) => (
+            paths
+                .first()
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            true,
+        ),
+        ("file", _) => (String::new(), true),
+        _ => (kv.value_text(), false),
+    };
+
+    http::KeyValue {
+        enabled: !kv.disabled,
+        file,
+        ..http::KeyValue::new(kv.key.clone(), value)
+    }
+}
+
+/// Imports a Postman v2.1 collection export as a new collection. Folders
+/// become folders, requests become saved requests and the collection
+/// variables become an environment of the same name, so the `{{var}}`
+/// references Postman uses keep working.
+pub fn import_collection(document: Value) -> Result<ImportSummary> {
+    let collection: Collection =
+        serde_json::from_value(document).map_err(|e| eyre!("Not a Postman collection: {}", e))?;
+    if !collection.info.schema.is_empty() && !collection.info.schema.contains("v2.") {
+        return Err(eyre!(
+            "Unsupported Postman collection schema: {}",
+            collection.info.schema
+        ));
+    }
+
+    let dir = collections::unique_folder_path(&collections_dir(), &collection.info.name);
+    fs::create_dir_all(&dir)?;
+    let mut summary = ImportSummary {
+        collection: collections::request_location(&dir),
+        requests: 0,
+        skipped: Vec::new(),
+        stripped: Vec::new(),
+        environment: None,
+    };
+    let auth = collection
+        .auth
+        .as_ref()
+        .map(convert_auth)
+        .unwrap_or_default();
+    import_items(&collection.item, &dir, &auth, &mut summary)?;
+
+    let variables: BTreeMap<String, String> = enabled(&collection.variable)
+        .map(|kv| (kv.key.clone(), kv.value_text()))
+        .collect();
+    if !variables.is_empty() {
+        summary.environment = Some(save_variables(&summary.collection, variables)?);
+    }
+
+    Ok(summary)
+}
+
+/// `auth` is the one set on the enclosing folder or collection.
+fn import_items(
+    items: &[Item],
+    dir: &Path,
+    auth: &Auth,
+    summary: &mut ImportSummary,
+) -> Result<()> {
+    for item in items {
+        let auth = item.auth.as_ref().map(convert_auth).unwrap_or(auth.clone());
+        if let Some(children) = &item.item {
+            let folder = collections::unique_folder_path(dir, &item.name);
+            fs::create_dir_all(&folder)?;
+            import_items(children, &folder, &auth, summary)?;
+        } else if let Some(request) = &item.request {
+            match convert_request(request, &auth) {
+                Ok(saved) => {
+                    let path = collections::unique_request_path(dir, &item.name);
+                    let stripped = collections::save_request(&path, &saved)?;
+                    summary.record_stripped(&item.name, stripped);
+                    summary.requests += 1;
+                }
+                Err(e) => summary.skipped.push(format!("{}: {}", item.name, e)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn convert_request(request: &Request, inherited_auth: &Auth) -> Result<SavedRequest> {
+    let (method, url, header, body, auth) = match request {
+        Request::Url(url) => {
+            return Ok(SavedRequest {
+                method: "GET".to_string(),
+                url: url.clone(),
+                auth: inherited_auth.clone(),
+                ..Default::default()
+            });
+        }
+        Request::Full(details) => (
+            details.method.to_uppercase(),
+            &details.url,
+            &details.header,
+            &details.body,
+            &details.auth,
+        ),
+    };
+    method
+        .parse::<RequestType>()
+        .map_err(|_| eyre!("unsupported method {}", method))?;
+
+    // Disabled headers are kept as disabled rows, like Postman shows them.
+    let mut headers: Vec<http::KeyValue> = header
+        .iter()
+        .map(|kv| http::KeyValue {
+            enabled: !kv.disabled,
+            ..http::KeyValue::new(kv.key.clone(), kv.value_text())
+        })
+        .collect();
+    let mut body_mode = BodyMode::Raw;
+    let mut form = Vec::new();
+    let mut body_file = String::new();
+    let body = match body {
+        Some(body) if body.mode == "raw" => {
+            let language = body
+                .options
+                .as_ref()
+                .and_then(|options| options.pointer("/raw/language"))
+                .and_then(Value::as_str);
+            let content_type = match language {
+                Some("json") => Some("application/json"),
+                Some("xml") => Some("application/xml"),
+                Some("html") => Some("text/html"),
+                _ => None,
+            };
+            if let Some(content_type) = content_type
+                && header_value(&headers, "content-type").is_none()
+            {
+                headers.push(http::KeyValue::new("Content-Type", content_type));
+            }
+            body.raw.clone()
+        }
+        Some(body) if body.mode == "urlencoded" => {
+            body_mode = BodyMode::FormUrlencoded;
+            form = body.urlencoded.iter().map(form_row).collect();
+            String::new()
+        }
+        Some(body) if body.mode == "formdata" => {
+            body_mode = BodyMode::Multipart;
+            form = body.formdata.iter().map(form_row).collect();
+            String::new()
+        }
+        Some(body) if body.mode == "file" => {
+            body_mode = BodyMode::File;
+            body_file = body.file["src"].as_str().unwrap_or_default().to_string();
+            String::new()
+        }
+        Some(body) if !body.mode.is_empty() => {
+            return Err(eyre!("unsupported body mode {}", body.mode));
+        }
+        _ => String::new(),
+    };
+
+    Ok(SavedRequest {
+        method,
+        url: url.as_ref().map(url_text).unwrap_or_default(),
+        headers,
+        body,
+        body_mode,
+        form,
+        body_file,
+        auth: auth
+            .as_ref()
+            .map(convert_auth)
+            .unwrap_or(inherited_auth.clone()),
+        ..Default::default()
+    })
+}
+
+/// Auth types other than Basic, Digest, Bearer, API key, OAuth 2.0 and AWS
+/// Signature are imported as no auth. OAuth 2.0 grants that need a browser become a Bearer token.
+fn convert_auth(auth: &PostmanAuth) -> Auth {
+    let setting = |settings: &[KeyValue], key: &str| {
+        settings
+            .iter()
+            .find(|kv| kv.key == key)
+            .map(KeyValue::value_text)
+            .unwrap_or_default()
+    };
+    match auth.kind.as_str() {
+        "basic" => Auth::Basic {
+            username: setting(&auth.basic, "username"),
+            password: setting(&auth.basic, "password"),
+        },
+        "digest" => Auth::Digest {
+            username: setting(&auth.digest, "username"),
+            password: setting(&auth.digest, "password"),
+        },
+        "bearer" => Auth::Bearer {
+            token: setting(&auth.bearer, "token"),
+        },
+        "apikey" => Auth::ApiKey {
+            name: setting(&auth.apikey, "key"),
+            value: setting(&auth.apikey, "value"),
+            location: if setting(&auth.apikey, "in") == "query" {
+                ApiKeyLocation::Query
+            } else {
+                ApiKeyLocation::Header
+            },
+        },
+        "oauth2" => {
+            let grant = match setting(&auth.oauth2, "grant_type").as_str() {
+                "client_credentials" => Grant::ClientCredentials,
+                "password_credentials" => Grant::Password,
+                _ => {
+                    return Auth::Bearer {
+                        token: setting(&auth.oauth2, "accessToken"),
+                    };
+                }
+            };
+            Auth::OAuth2(OAuth2 {
+                grant,
+                token_url: setting(&auth.oauth2, "accessTokenUrl"),
+                client_id: setting(&auth.oauth2, "clientId"),
+                client_secret: setting(&auth.oauth2, "clientSecret"),
+                scope: setting(&auth.oauth2, "scope"),
+                username: setting(&auth.oauth2, "username"),
+                password: setting(&auth.oauth2, "password"),
+                client_auth: if setting(&auth.oauth2, "client_authentication") == "body" {
+                    ClientAuth::Body
+                } else {
+                    ClientAuth::Header
+                },
+                ..Default::default()
+            })
+        }
+        "awsv4" => Auth::AwsSigV4 {
+            access_key: setting(&auth.awsv4, "accessKey"),
+            secret_key: setting(&auth.awsv4, "secretKey"),
+            session_token: setting(&auth.awsv4, "sessionToken"),
+            region: setting(&auth.awsv4, "region"),
+            service: setting(&auth.awsv4, "service"),
+        },
+        _ => Auth::None,
+    }
+}
+
+fn url_text(url: &Url) -> String {
+    match url {
+        Url::Raw(raw) => raw.clone(),
+        Url::Parts(parts) => {
+            let UrlParts {
+                raw,
+                protocol,
+                host,
+                port,
+                path,
+                query,
+            } = parts.as_ref();
+            if let Some(raw) = raw {
+                return raw.clone();
+            }
+            let mut url = String::new();
+            if let Some(protocol) = protocol {
+                url.push_str(&format!("{}://", protocol));
+            }
+            url.push_str(&join_parts(host, "."));
+            if let Some(port) = port {
+                url.push_str(&format!(":{}", port));
+            }
+            let path = join_parts(path, "/");
+            if !path.is_empty() {
+                url.push('/');
+                url.push_str(path.trim_start_matches('/'));
+            }
+            let query: Vec<String> = enabled(query)
+                .map(|kv| format!("{}={}", kv.key, kv.value_text()))
+                .collect();
+            if !query.is_empty() {
+                url.push('?');
+                url.push_str(&query.join("&"));
+            }
+            url
+        }
+    }
+}
+
+/// Host and path can be a plain string or a list of segments, path segments
+/// can also be `{"value": ...}` objects.
+fn join_parts(parts: &Value, separator: &str) -> String {
+    match parts {
+        Value::String(part) => part.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(|part| match part {
+                Value::String(part) => part.as_str(),
+                part => part["value"].as_str().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>()
+            .join(separator),
+        _ => String::new(),
+    }
+}
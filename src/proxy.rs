@@ -0,0 +1,380 @@
+use crate::storage::data_dir;
+use color_eyre::{Result, eyre::eyre};
+use reqwest::{ClientBuilder, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, net::IpAddr, path::PathBuf};
+
+/// Proxy settings, the global ones or an environment's. An environment with
+/// proxy settings of its own doesn't use the global ones at all.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ProxySettings {
+    /// `http://`, `https://`, `socks5://` or `socks5h://`, the last having the
+    /// proxy resolve host names. Without a scheme it's an HTTP proxy.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+    /// Hosts reached directly, comma separated, in the `NO_PROXY` format.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub no_proxy: String,
+    /// Connects directly when no proxy URL is set, rather than through the
+    /// proxy of `HTTP_PROXY`, `HTTPS_PROXY` or `ALL_PROXY`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignore_environment: bool,
+}
+
+/// The rows of the Proxy block in the Environments tab.
+pub const FIELDS: [&str; 5] = [
+    "Proxy URL",
+    "Username",
+    "Password",
+    "No proxy",
+    "HTTP_PROXY/NO_PROXY",
+];
+
+/// The row that's switched between honored and ignored rather than edited.
+pub const ENVIRONMENT_VARIABLES: usize = 4;
+
+impl ProxySettings {
+    pub fn is_default(&self) -> bool {
+        *self == ProxySettings::default()
+    }
+
+    /// The value of a row as the Environments tab shows it, the password
+    /// masked.
+    pub fn value(&self, field: usize) -> String {
+        match field {
+            0 => self.url.clone(),
+            1 => self.username.clone(),
+            2 if self.password.is_empty() => String::new(),
+            2 => "••••••••".to_string(),
+            3 => self.no_proxy.clone(),
+            _ if !self.url.trim().is_empty() => "overridden by the proxy URL".to_string(),
+            _ if self.ignore_environment => "ignored".to_string(),
+            _ => "honored".to_string(),
+        }
+    }
+
+    /// The value the edit prompt starts with.
+    pub fn text(&self, field: usize) -> &str {
+        match field {
+            0 => &self.url,
+            1 => &self.username,
+            2 => &self.password,
+            _ => &self.no_proxy,
+        }
+    }
+
+    pub fn set_text(&mut self, field: usize, value: String) {
+        match field {
+            0 => self.url = value,
+            1 => self.username = value,
+            2 => self.password = value,
+            3 => self.no_proxy = value,
+            _ => {}
+        }
+    }
+
+    /// The proxy a request to `url` goes through, with its credentials.
+    /// `None` to connect directly.
+    pub fn route(&self, url: &Url) -> Option<Url> {
+        self.route_with(url, environment_variable)
+    }
+
+    /// `route`, reading the environment variables with `variable`.
+    fn route_with(&self, url: &Url, variable: impl Fn(&[&str]) -> Option<String>) -> Option<Url> {
+        let host = url.host_str()?;
+        if !self.url.trim().is_empty() {
+            if bypasses(&self.no_proxy, host) {
+                return None;
+            }
+            let mut proxy = proxy_url(&self.url).ok()?;
+            if !self.username.is_empty() {
+                proxy.set_username(&self.username).ok()?;
+                proxy.set_password(Some(&self.password)).ok()?;
+            }
+            return Some(proxy);
+        }
+        if self.ignore_environment {
+            return None;
+        }
+
+        let proxy = if url.scheme() == "https" {
+            variable(&["HTTPS_PROXY", "ALL_PROXY"])
+        } else {
+            variable(&["HTTP_PROXY", "ALL_PROXY"])
+        }?;
+        if variable(&["NO_PROXY"]).is_some_and(|no_proxy| bypasses(&no_proxy, host)) {
+            return None;
+        }
+        proxy_url(&proxy).ok()
+    }
+
+    /// Routes a client's requests through the proxy. The environment
+    /// variables are only read here, reqwest's own lookup is replaced.
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder> {
+        if !self.url.trim().is_empty() {
+            proxy_url(&self.url)?;
+        }
+        let settings = self.clone();
+        Ok(builder.proxy(Proxy::custom(move |url| settings.route(url))))
+    }
+}
+
+/// The settings a request uses: the environment's if it has any of its own,
+/// the global ones otherwise.
+pub fn effective(environment: Option<&ProxySettings>, global: &ProxySettings) -> ProxySettings {
+    environment
+        .filter(|proxy| !proxy.is_default())
+        .unwrap_or(global)
+        .clone()
+}
+
+fn proxy_url(text: &str) -> Result<Url> {
+    let text = text.trim();
+    let url = if text.contains("://") {
+        Url::parse(text)
+    } else {
+        Url::parse(&format!("http://{}", text))
+    }
+    .map_err(|e| eyre!("Invalid proxy URL {}: {}", text, e))?;
+    match url.scheme() {
+        "http" | "https" | "socks5" | "socks5h" => Ok(url),
+        scheme => Err(eyre!(
+            "Unsupported proxy scheme {}, expected http, https, socks5 or socks5h",
+            scheme
+        )),
+    }
+}
+
+/// The first of the variables that's set, upper or lower case.
+fn environment_variable(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| {
+            env::var(name)
+                .or_else(|_| env::var(name.to_lowercase()))
+                .ok()
+        })
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// `host` is in a no-proxy list: `*`, a domain along with its subdomains, an
+/// IP address or a CIDR range.
+fn bypasses(no_proxy: &str, host: &str) -> bool {
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+    let ip = host.parse::<IpAddr>().ok();
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().to_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            if let Some(ip) = ip {
+                return in_range(ip, &entry);
+            }
+            let domain = entry.trim_start_matches('.');
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+}
+
+fn in_range(ip: IpAddr, entry: &str) -> bool {
+    let (address, prefix) = match entry.split_once('/') {
+        Some((address, prefix)) => (address, prefix.parse::<u32>().ok()),
+        None => (entry, None),
+    };
+    let Ok(address) = address.parse::<IpAddr>() else {
+        return false;
+    };
+    let (ip, address, bits) = match (ip, address) {
+        (IpAddr::V4(ip), IpAddr::V4(address)) => {
+            (u32::from(ip) as u128, u32::from(address) as u128, 32)
+        }
+        (IpAddr::V6(ip), IpAddr::V6(address)) => (u128::from(ip), u128::from(address), 128),
+        _ => return false,
+    };
+    let shift = bits - prefix.unwrap_or(bits).min(bits);
+    shift == bits || ip >> shift == address >> shift
+}
+
+fn global_path() -> PathBuf {
+    data_dir().join("proxy.json")
+}
+
+/// The proxy settings used without an environment, or with one that has
+/// none. Kept in the data directory, they belong to the machine.
+pub fn load_global() -> Result<ProxySettings> {
+    let path = global_path();
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| eyre!("Could not read {}: {}", path.display(), e)),
+        Err(_) => Ok(ProxySettings::default()),
+    }
+}
+
+pub fn save_global(settings: &ProxySettings) -> Result<()> {
+    let path = global_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut contents = serde_json::to_string_pretty(settings)?;
+    contents.push('\n');
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn url(text: &str) -> Url {
+        Url::parse(text).unwrap()
+    }
+
+    #[test]
+    fn domains_cover_their_subdomains() {
+        assert!(bypasses("example.com", "example.com"));
+        assert!(bypasses("example.com", "api.example.com"));
+        assert!(bypasses(".example.com", "api.EXAMPLE.com"));
+        assert!(bypasses("other.org, example.com", "example.com"));
+        assert!(!bypasses("example.com", "badexample.com"));
+        assert!(!bypasses("api.example.com", "example.com"));
+        assert!(!bypasses("", "example.com"));
+    }
+
+    #[test]
+    fn star_bypasses_every_host() {
+        assert!(bypasses("*", "example.com"));
+        assert!(bypasses("localhost, *", "10.1.2.3"));
+    }
+
+    #[test]
+    fn addresses_match_exactly_or_by_range() {
+        assert!(bypasses("10.0.0.0/8", "10.1.2.3"));
+        assert!(!bypasses("10.0.0.0/8", "11.0.0.1"));
+        assert!(bypasses("192.168.1.7", "192.168.1.7"));
+        assert!(!bypasses("192.168.1.7", "192.168.1.8"));
+        assert!(bypasses("fd00::/8", "[fd12::1]"));
+        assert!(!bypasses("fd00::/8", "[fe80::1]"));
+        // A domain entry doesn't match an address.
+        assert!(!bypasses("example.com", "10.1.2.3"));
+    }
+
+    #[test]
+    fn in_range_handles_prefix_edges_and_families() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+        assert!(in_range(ip("203.0.113.9"), "0.0.0.0/0"));
+        assert!(in_range(ip("203.0.113.9"), "203.0.113.9/32"));
+        assert!(!in_range(ip("203.0.113.9"), "203.0.113.8/32"));
+        assert!(in_range(ip("203.0.113.9"), "203.0.113.0/24"));
+        // A prefix longer than the address is an exact match.
+        assert!(!in_range(ip("203.0.113.9"), "203.0.113.0/99"));
+        assert!(in_range(ip("203.0.113.9"), "203.0.113.9/99"));
+        assert!(!in_range(ip("::1"), "127.0.0.1/8"));
+        assert!(!in_range(ip("127.0.0.1"), "not an address"));
+    }
+
+    #[test]
+    fn proxy_url_is_used_with_its_credentials_unless_bypassed() {
+        let settings = ProxySettings {
+            url: "proxy.corp:3128".to_string(),
+            username: "me".to_string(),
+            password: "pw".to_string(),
+            no_proxy: "internal.corp".to_string(),
+            ..Default::default()
+        };
+        let no_variables = |_: &[&str]| None;
+
+        let proxy = settings
+            .route_with(&url("https://example.com/"), no_variables)
+            .unwrap();
+        assert_eq!(proxy.as_str(), "http://me:pw@proxy.corp:3128/");
+        assert!(
+            settings
+                .route_with(&url("https://api.internal.corp/"), no_variables)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn environment_variables_are_the_fallback() {
+        let variables = HashMap::from([
+            ("HTTP_PROXY", "http://plain:8080"),
+            ("ALL_PROXY", "socks5h://all:1080"),
+            ("NO_PROXY", "localhost,10.0.0.0/8"),
+        ]);
+        let variable = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| variables.get(name).map(|value| value.to_string()))
+        };
+        let route = |settings: &ProxySettings, target: &str| {
+            settings
+                .route_with(&url(target), variable)
+                .map(|proxy| proxy.to_string())
+        };
+
+        let settings = ProxySettings::default();
+        assert_eq!(
+            route(&settings, "http://example.com/").as_deref(),
+            Some("http://plain:8080/")
+        );
+        assert_eq!(
+            route(&settings, "https://example.com/").as_deref(),
+            Some("socks5h://all:1080")
+        );
+        assert_eq!(route(&settings, "http://localhost:3000/"), None);
+        assert_eq!(route(&settings, "http://10.2.3.4/"), None);
+
+        let ignoring = ProxySettings {
+            ignore_environment: true,
+            ..Default::default()
+        };
+        assert_eq!(route(&ignoring, "http://example.com/"), None);
+
+        // A proxy URL of its own overrides the variables, NO_PROXY included.
+        let own = ProxySettings {
+            url: "http://own:3128".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            route(&own, "http://localhost:3000/").as_deref(),
+            Some("http://own:3128/")
+        );
+    }
+
+    #[test]
+    fn environment_settings_replace_the_global_ones() {
+        let global = ProxySettings {
+            url: "http://global:3128".to_string(),
+            ..Default::default()
+        };
+        let local = ProxySettings {
+            url: "http://localhost:8888".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(effective(None, &global).url, global.url);
+        assert_eq!(
+            effective(Some(&ProxySettings::default()), &global).url,
+            global.url
+        );
+        assert_eq!(effective(Some(&local), &global).url, local.url);
+        // Ignoring the variables is a setting of its own, not a fallback.
+        let direct = ProxySettings {
+            ignore_environment: true,
+            ..Default::default()
+        };
+        let settings = effective(Some(&direct), &global);
+        assert!(settings.url.is_empty() && settings.ignore_environment);
+    }
+}
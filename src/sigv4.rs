@@ -0,0 +1,406 @@
+use chrono::{DateTime, Utc};
+use color_eyre::{Result, eyre::eyre};
+use hmac::{Hmac, Mac};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use reqwest::{
+    Url,
+    header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue},
+};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Sent in place of the payload hash when the body can't be hashed up front,
+/// multipart forms are only assembled while they're sent.
+pub const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Everything but the unreserved characters of RFC 3986, as SigV4 encodes.
+const SIGV4_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// The credentials and scope a request is signed with.
+pub struct Credentials<'a> {
+    pub access_key: &'a str,
+    pub secret_key: &'a str,
+    pub session_token: &'a str,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Hashes a file body in chunks, so large uploads aren't read into memory.
+pub async fn sha256_file_hex(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| eyre!("Could not open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn encode(text: &str) -> String {
+    utf8_percent_encode(text, SIGV4_ENCODE_SET).to_string()
+}
+
+/// The path as sent, with each segment encoded again. S3 is the one service
+/// that takes it encoded only once.
+fn canonical_uri(url: &Url, service: &str) -> String {
+    let path = match url.path() {
+        "" => "/",
+        path => path,
+    };
+    if service == "s3" {
+        return path.to_string();
+    }
+    path.split('/').map(encode).collect::<Vec<_>>().join("/")
+}
+
+/// Query parameters decoded, then encoded the SigV4 way and sorted.
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |text| percent_decode_str(text).decode_utf8_lossy().to_string();
+            (encode(&decode(name)), encode(&decode(value)))
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// `Host` as the client will send it, the port only when it isn't the
+/// default one.
+pub fn host_header(url: &Url) -> Result<String> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| eyre!("{} has no host to sign", url))?;
+    Ok(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+/// The `Authorization` header value for a request. Every header in `headers`
+/// is signed, they must include `host` and `x-amz-date`.
+pub fn authorization(
+    credentials: &Credentials,
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    payload_hash: &str,
+    time: DateTime<Utc>,
+) -> String {
+    let mut headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            (name.to_lowercase(), value)
+        })
+        .collect();
+    headers.sort_by(|a, b| a.0.cmp(&b.0));
+    // Repeated headers are signed as one, their values joined by commas.
+    let mut canonical_headers: Vec<(String, String)> = Vec::new();
+    for (name, value) in headers {
+        match canonical_headers.last_mut() {
+            Some((last, values)) if *last == name => {
+                values.push(',');
+                values.push_str(&value);
+            }
+            _ => canonical_headers.push((name, value)),
+        }
+    }
+    let signed_headers = canonical_headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        canonical_uri(url, credentials.service),
+        canonical_query(url),
+        canonical_headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect::<String>(),
+        signed_headers,
+        payload_hash,
+    );
+
+    let date = time.format("%Y%m%d").to_string();
+    let scope = format!(
+        "{}/{}/{}/aws4_request",
+        date, credentials.region, credentials.service
+    );
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        ALGORITHM,
+        time.format("%Y%m%dT%H%M%SZ"),
+        scope,
+        sha256_hex(canonical_request.as_bytes()),
+    );
+
+    let key = [
+        date.as_str(),
+        credentials.region,
+        credentials.service,
+        "aws4_request",
+    ]
+    .into_iter()
+    .fold(
+        format!("AWS4{}", credentials.secret_key).into_bytes(),
+        |key, part| hmac(&key, part),
+    );
+    let signature = hex::encode(hmac(&key, &string_to_sign));
+
+    format!(
+        "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+        ALGORITHM, credentials.access_key, scope, signed_headers, signature
+    )
+}
+
+/// Signs a request about to be sent. Adds `X-Amz-Date`,
+/// `X-Amz-Content-Sha256`, the session token if there is one, and the
+/// `Authorization` header. `Host`, `Content-Type` and every `X-Amz-*`
+/// header are signed.
+pub fn sign(
+    credentials: &Credentials,
+    method: &str,
+    url: &str,
+    headers: &mut HeaderMap,
+    payload_hash: &str,
+) -> Result<()> {
+    let url = Url::parse(url)?;
+    let time = Utc::now();
+    headers.insert(
+        HeaderName::from_static("x-amz-date"),
+        HeaderValue::from_str(&time.format("%Y%m%dT%H%M%SZ").to_string())?,
+    );
+    headers.insert(
+        HeaderName::from_static("x-amz-content-sha256"),
+        HeaderValue::from_str(payload_hash)?,
+    );
+    if !credentials.session_token.is_empty() {
+        headers.insert(
+            HeaderName::from_static("x-amz-security-token"),
+            HeaderValue::from_str(credentials.session_token)?,
+        );
+    }
+
+    let mut signed = vec![("host".to_string(), host_header(&url)?)];
+    for (name, value) in headers.iter() {
+        if name.as_str() == "content-type" || name.as_str().starts_with("x-amz-") {
+            signed.push((name.to_string(), value.to_str()?.to_string()));
+        }
+    }
+    let authorization = authorization(credentials, method, &url, &signed, payload_hash, time);
+    headers.insert(AUTHORIZATION, HeaderValue::from_str(&authorization)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The credentials and time of the AWS SigV4 test suite.
+    const CREDENTIALS: Credentials = Credentials {
+        access_key: "AKIDEXAMPLE",
+        secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        session_token: "",
+        region: "us-east-1",
+        service: "service",
+    };
+
+    const SESSION_TOKEN: &str = "AQoDYXdzEPT//////////wEXAMPLEtc764bNrC9SAPBSM22wDOk4x4HIZ8j4FZTwdQWLWsKWHGBuFqwAeMicRXmxfpSPfIeoIYRqTflfKD8YUuwthAx7mSEI/qkPpKPi/kMcGdQrmGdeehM4IC1NtBmUpp2wUE8phUZampKsburEDy0KPkyQDYwT7WZ0wq5VSXDvp75YU9HFvlRd8Tx6q6fE8YQcHNVXAkiY9q6d+xo0rKwT38xVqr7ZD0u0iPPkUL64lIZbqBAz+scqKmlzm8FDrypNC9Yjc8fPOLn9FX9KSYvKTr4rvx3iSIlTJabIQwj2ICCR/oLxBA==";
+
+    /// Signs `host` and `x-amz-date` along with `headers`.
+    fn sign(
+        credentials: &Credentials,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> String {
+        let url = Url::parse(url).unwrap();
+        let mut signed = vec![
+            ("Host".to_string(), host_header(&url).unwrap()),
+            ("X-Amz-Date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        signed.extend(
+            headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        );
+        let time = "2015-08-30T12:36:00Z".parse().unwrap();
+        authorization(credentials, method, &url, &signed, &sha256_hex(body), time)
+    }
+
+    fn expected(signed_headers: &str, signature: &str) -> String {
+        format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders={}, Signature={}",
+            signed_headers, signature
+        )
+    }
+
+    #[test]
+    fn get_vanilla() {
+        assert_eq!(
+            sign(
+                &CREDENTIALS,
+                "GET",
+                "https://example.amazonaws.com/",
+                &[],
+                b""
+            ),
+            expected(
+                "host;x-amz-date",
+                "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+            )
+        );
+    }
+
+    #[test]
+    fn get_vanilla_query_order_key() {
+        assert_eq!(
+            sign(
+                &CREDENTIALS,
+                "GET",
+                "https://example.amazonaws.com/?Param2=value2&Param1=value1",
+                &[],
+                b""
+            ),
+            expected(
+                "host;x-amz-date",
+                "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+            )
+        );
+    }
+
+    /// The suite encodes the path once, as S3 takes it. Other services take
+    /// it encoded twice, as botocore signs it, so this is botocore's value.
+    #[test]
+    fn get_utf8() {
+        assert_eq!(
+            sign(
+                &CREDENTIALS,
+                "GET",
+                "https://example.amazonaws.com/ሴ",
+                &[],
+                b""
+            ),
+            expected(
+                "host;x-amz-date",
+                "697b34846207a3f72246f99d74ae1ee4fe54f44bb06730c58a0d339eb079596d"
+            )
+        );
+    }
+
+    #[test]
+    fn post_x_www_form_urlencoded() {
+        assert_eq!(
+            sign(
+                &CREDENTIALS,
+                "POST",
+                "https://example.amazonaws.com/",
+                &[("Content-Type", "application/x-www-form-urlencoded")],
+                b"Param1=value1"
+            ),
+            expected(
+                "content-type;host;x-amz-date",
+                "ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+            )
+        );
+    }
+
+    #[test]
+    fn post_vanilla_query() {
+        assert_eq!(
+            sign(
+                &CREDENTIALS,
+                "POST",
+                "https://example.amazonaws.com/?Param1=value1",
+                &[],
+                b""
+            ),
+            expected(
+                "host;x-amz-date",
+                "28038455d6de14eafc1f9222cf5aa6f1a96197d7deb8263271d420d138af7f11"
+            )
+        );
+    }
+
+    #[test]
+    fn get_vanilla_with_session_token() {
+        let credentials = Credentials {
+            session_token: SESSION_TOKEN,
+            ..CREDENTIALS
+        };
+        assert_eq!(
+            sign(
+                &credentials,
+                "GET",
+                "https://example.amazonaws.com/",
+                &[("X-Amz-Security-Token", SESSION_TOKEN)],
+                b""
+            ),
+            expected(
+                "host;x-amz-date;x-amz-security-token",
+                "c8db8b9676d526f735dac5330f17623554c6cad1e2980d321903e9a3884c051b"
+            )
+        );
+    }
+
+    #[test]
+    fn s3_path_is_not_encoded_again() {
+        let url = Url::parse("https://examplebucket.s3.amazonaws.com/my%20file").unwrap();
+        assert_eq!(canonical_uri(&url, "s3"), "/my%20file");
+        assert_eq!(canonical_uri(&url, "service"), "/my%2520file");
+
+        let credentials = Credentials {
+            service: "s3",
+            ..CREDENTIALS
+        };
+        assert_eq!(
+            sign(
+                &credentials,
+                "GET",
+                url.as_str(),
+                &[("X-Amz-Content-Sha256", &sha256_hex(b""))],
+                b""
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature=efb8d981aae5b9915890b1651c4ab4ed91d8a00f1955ea7d92324e5686f241a6"
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Directory for files that belong to this machine rather than to a project,
+/// e.g. the request history. `PINGPONG_DATA_DIR` overrides the platform default.
+pub fn data_dir() -> PathBuf {
+    if let Ok(dir) = env::var("PINGPONG_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    dirs::data_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join("pingpong")
+}
+
+/// Directory for files meant to be shared with a project, e.g. saved request
+/// collections. Defaults to `.pingpong` in the working directory so it can be
+/// committed alongside the code, `PINGPONG_WORKSPACE` overrides it.
+pub fn workspace_dir() -> PathBuf {
+    if let Ok(dir) = env::var("PINGPONG_WORKSPACE") {
+        return PathBuf::from(dir);
+    }
+
+    PathBuf::from(".pingpong")
+}
+
+/// Expands a leading `~` in a path typed by the user.
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~")
+        && (rest.is_empty() || rest.starts_with('/'))
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest.trim_start_matches('/'));
+    }
+
+    Path::new(path).to_path_buf()
+}
@@ -0,0 +1,469 @@
+use crate::{DisplayString, http::KeyValue, storage::expand_home};
+use crossterm::event::KeyCode;
+use ratatui::{prelude::*, widgets::*};
+use std::{fs, path::PathBuf};
+
+/// Header names offered by autocomplete.
+static COMMON_HEADERS: [&str; 27] = [
+    "Accept",
+    "Accept-Encoding",
+    "Accept-Language",
+    "Authorization",
+    "Cache-Control",
+    "Connection",
+    "Content-Disposition",
+    "Content-Encoding",
+    "Content-Length",
+    "Content-Type",
+    "Cookie",
+    "DNT",
+    "Forwarded",
+    "If-Match",
+    "If-Modified-Since",
+    "If-None-Match",
+    "Origin",
+    "Pragma",
+    "Range",
+    "Referer",
+    "User-Agent",
+    "X-API-Key",
+    "X-Correlation-ID",
+    "X-Forwarded-For",
+    "X-Forwarded-Proto",
+    "X-Request-ID",
+    "X-Requested-With",
+];
+
+static MEDIA_TYPES: [&str; 7] = [
+    "application/json",
+    "application/xml",
+    "application/x-www-form-urlencoded",
+    "multipart/form-data",
+    "text/plain",
+    "text/html",
+    "*/*",
+];
+
+/// Values offered by autocomplete for a few well known headers.
+fn common_values(name: &str) -> &'static [&'static str] {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "accept" | "content-type" => &MEDIA_TYPES,
+        "accept-encoding" => &["gzip, deflate, br", "gzip", "identity"],
+        "authorization" => &["Bearer ", "Basic "],
+        "cache-control" => &["no-cache", "no-store", "max-age=0"],
+        "connection" => &["keep-alive", "close"],
+        _ => &[],
+    }
+}
+
+/// Files and folders whose path starts with `typed`, for picking a file to
+/// upload. Hidden entries are only offered once a `.` has been typed.
+pub fn path_completions(typed: &str) -> Vec<String> {
+    let (dir, prefix) = match typed.rfind('/') {
+        Some(index) => typed.split_at(index + 1),
+        None => ("", typed),
+    };
+    let search_dir = if dir.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_home(dir)
+    };
+    let Ok(entries) = fs::read_dir(search_dir) else {
+        return Vec::new();
+    };
+
+    let mut completions: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect();
+    completions.sort();
+    completions
+}
+
+/// What the rows of a table are, which decides its title, autocomplete and
+/// validation.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TableKind {
+    Headers,
+    Params,
+    FormUrlencoded,
+    Multipart,
+}
+
+impl TableKind {
+    fn title(self) -> &'static str {
+        match self {
+            TableKind::Headers => "Headers",
+            TableKind::Params => "Params",
+            TableKind::FormUrlencoded => "Request Body - form-urlencoded",
+            TableKind::Multipart => "Request Body - multipart",
+        }
+    }
+
+    fn placeholder(self) -> &'static str {
+        match self {
+            TableKind::Headers => "No headers. Press [a] to add one.",
+            TableKind::Params => "No query parameters. Press [a] to add one.",
+            TableKind::FormUrlencoded | TableKind::Multipart => {
+                "No fields. Press [a] to add one, [m] to change the body mode."
+            }
+        }
+    }
+
+    fn error(self, row: &KeyValue) -> Option<String> {
+        match self {
+            TableKind::Headers => row.header_error(),
+            TableKind::FormUrlencoded if row.file => {
+                Some("Files can only be sent in a multipart body".to_string())
+            }
+            TableKind::Multipart if row.file && row.value.is_empty() => {
+                Some(format!("Pick a file to send as {}", row.name))
+            }
+            TableKind::Params | TableKind::FormUrlencoded | TableKind::Multipart => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Name,
+    Value,
+}
+
+/// A table of name/value rows, like the Headers and Params blocks of the
+/// Request/Reply tab. Rows are edited one cell at a time, name first and then
+/// value.
+pub struct TableEditor {
+    pub rows: Vec<KeyValue>,
+    kind: TableKind,
+    state: TableState,
+    editing: Option<Column>,
+    input: DisplayString,
+    /// Which autocomplete suggestion is shown, cycled with up/down.
+    suggestion: usize,
+    /// Whether the row being edited was just added, so esc removes it again.
+    new_row: bool,
+}
+
+impl TableEditor {
+    pub fn new(kind: TableKind, rows: Vec<KeyValue>) -> Self {
+        let mut editor = Self {
+            rows: Vec::new(),
+            kind,
+            state: TableState::default(),
+            editing: None,
+            input: DisplayString::new(String::new()),
+            suggestion: 0,
+            new_row: false,
+        };
+        editor.set_rows(rows);
+        editor
+    }
+
+    /// Replaces the rows, keeping the selection where it was if it still fits.
+    pub fn set_rows(&mut self, rows: Vec<KeyValue>) {
+        self.rows = rows;
+        self.editing = None;
+        self.new_row = false;
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select((!self.rows.is_empty()).then(|| selected.min(self.rows.len() - 1)));
+    }
+
+    /// Switches between kinds that share rows, like the two form body modes.
+    pub fn set_kind(&mut self, kind: TableKind) {
+        self.kind = kind;
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        // A cell can't span lines, keep the first one.
+        self.input.add_str(text.lines().next().unwrap_or_default());
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.state.selected().filter(|&i| i < self.rows.len())
+    }
+
+    fn start_editing(&mut self, column: Column) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        let row = &self.rows[index];
+        let value = match column {
+            Column::Name => row.name.clone(),
+            Column::Value => row.value.clone(),
+        };
+        self.input.update_value(value);
+        self.input.edit_mode = true;
+        self.editing = Some(column);
+        self.suggestion = 0;
+    }
+
+    fn add_row(&mut self) {
+        let index = self.selected().map(|i| i + 1).unwrap_or(self.rows.len());
+        self.rows.insert(index, KeyValue::new("", ""));
+        self.state.select(Some(index));
+        self.new_row = true;
+        self.start_editing(Column::Name);
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        let (Some(column), Some(index)) = (self.editing, self.selected()) else {
+            return Vec::new();
+        };
+        let typed = self.input.value.to_ascii_lowercase();
+        let candidates: Vec<String> = match (self.kind, column) {
+            (TableKind::Headers, Column::Name) => {
+                COMMON_HEADERS.iter().map(|c| c.to_string()).collect()
+            }
+            (TableKind::Headers, Column::Value) => common_values(&self.rows[index].name)
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+            (TableKind::Multipart, Column::Value) if self.rows[index].file => {
+                path_completions(&self.input.value)
+            }
+            _ => Vec::new(),
+        };
+
+        candidates
+            .into_iter()
+            .filter(|c| c.to_ascii_lowercase().starts_with(&typed) && c.len() > typed.len())
+            .collect()
+    }
+
+    fn current_suggestion(&self) -> Option<String> {
+        let mut suggestions = self.suggestions();
+        let count = suggestions.len();
+        (count > 0).then(|| suggestions.swap_remove(self.suggestion % count))
+    }
+
+    fn finish_cell(&mut self) {
+        let (Some(index), Some(column)) = (self.selected(), self.editing) else {
+            return;
+        };
+        let value = self.input.value.clone();
+        match column {
+            Column::Name => {
+                self.rows[index].name = value.trim().to_string();
+                self.start_editing(Column::Value);
+            }
+            Column::Value => {
+                self.rows[index].value = value;
+                self.stop_editing();
+            }
+        }
+    }
+
+    fn stop_editing(&mut self) {
+        self.editing = None;
+        self.input.edit_mode = false;
+        self.new_row = false;
+    }
+
+    fn cancel_editing(&mut self) {
+        if self.new_row
+            && let Some(index) = self.selected()
+            && self.rows[index].name.is_empty()
+        {
+            self.rows.remove(index);
+            self.state
+                .select((!self.rows.is_empty()).then(|| index.saturating_sub(1)));
+        }
+        self.stop_editing();
+    }
+
+    /// Handles keys while the table's block is active, returning whether the
+    /// key was consumed. Moving past the first or last row isn't, so the
+    /// arrows still move between blocks.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.is_editing() {
+            match code {
+                KeyCode::Char(c) => {
+                    self.input.add_char(c);
+                    self.suggestion = 0;
+                }
+                KeyCode::Backspace => {
+                    self.input.remove_last_char();
+                    self.suggestion = 0;
+                }
+                KeyCode::Tab => {
+                    if let Some(suggestion) = self.current_suggestion() {
+                        self.input.update_value(suggestion);
+                        self.suggestion = 0;
+                    }
+                }
+                KeyCode::Down => self.suggestion += 1,
+                KeyCode::Up => self.suggestion = self.suggestion.saturating_sub(1),
+                KeyCode::Enter => self.finish_cell(),
+                KeyCode::Esc => self.cancel_editing(),
+                _ => {}
+            }
+            return true;
+        }
+
+        let selected = self.selected();
+        match code {
+            KeyCode::Down | KeyCode::Char('j')
+                if selected.is_some_and(|i| i + 1 < self.rows.len()) =>
+            {
+                self.state.select(selected.map(|i| i + 1));
+            }
+            KeyCode::Up | KeyCode::Char('k') if selected.is_some_and(|i| i > 0) => {
+                self.state.select(selected.map(|i| i - 1));
+            }
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if selected.is_some() {
+                    self.start_editing(Column::Name);
+                } else {
+                    self.add_row();
+                }
+            }
+            KeyCode::Char('a') => self.add_row(),
+            KeyCode::Char('x') | KeyCode::Delete => {
+                if let Some(index) = selected {
+                    self.rows.remove(index);
+                    self.state
+                        .select((!self.rows.is_empty()).then(|| index.min(self.rows.len() - 1)));
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(index) = selected {
+                    self.rows[index].enabled = !self.rows[index].enabled;
+                }
+            }
+            KeyCode::Char('f') if self.kind == TableKind::Multipart => {
+                if let Some(index) = selected {
+                    self.rows[index].file = !self.rows[index].file;
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn editing_cell(&self) -> Cell<'static> {
+        let mut spans = vec![
+            Span::styled(self.input.value.clone(), Style::default().fg(Color::White)),
+            Span::raw("█"),
+        ];
+        if let Some(suggestion) = self.current_suggestion() {
+            spans.push(Span::styled(
+                suggestion[self.input.value.len()..].to_string(),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Cell::from(Line::from(spans))
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, active: bool) {
+        let invalid = self
+            .rows
+            .iter()
+            .filter(|row| row.enabled && self.kind.error(row).is_some())
+            .count();
+        let mut title = self.kind.title().to_string();
+        if self.is_editing() {
+            title.push_str(" - Editing");
+        }
+        if invalid > 0 {
+            title.push_str(&format!(" - {} invalid", invalid));
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_style(
+                Style::default()
+                    .fg(Color::LightYellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .border_style(if active {
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::LightBlue)
+            });
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if self.rows.is_empty() {
+            frame.render_widget(
+                Paragraph::new(self.kind.placeholder()).style(Style::default().fg(Color::DarkGray)),
+                inner,
+            );
+            return;
+        }
+
+        let selected_error = self
+            .selected()
+            .and_then(|i| self.kind.error(&self.rows[i]))
+            .filter(|_| active);
+        let [table_area, error_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(if selected_error.is_some() { 1 } else { 0 }),
+        ])
+        .areas(inner);
+
+        let selected = self.selected();
+        let rows: Vec<Row> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let style = if !row.enabled {
+                    Style::default().fg(Color::DarkGray)
+                } else if self.kind.error(row).is_some() {
+                    Style::default().fg(Color::LightRed)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                let checkbox = if row.enabled { "[x]" } else { "[ ]" };
+                let editing = self.editing.filter(|_| Some(index) == selected);
+                let name = match editing {
+                    Some(Column::Name) => self.editing_cell(),
+                    _ => Cell::from(row.name.clone()),
+                };
+                let value = match editing {
+                    Some(Column::Value) => self.editing_cell(),
+                    _ if row.file => Cell::from(format!("file: {}", row.value)),
+                    _ => Cell::from(row.value.clone()),
+                };
+                Row::new(vec![Cell::from(checkbox), name, value]).style(style)
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Percentage(40),
+                Constraint::Fill(1),
+            ],
+        )
+        .row_highlight_style(if active {
+            Style::default().bg(Color::DarkGray)
+        } else {
+            Style::default()
+        });
+        frame.render_stateful_widget(table, table_area, &mut self.state);
+
+        if let Some(error) = selected_error {
+            frame.render_widget(
+                Paragraph::new(error).style(Style::default().fg(Color::LightRed)),
+                error_area,
+            );
+        }
+    }
+}
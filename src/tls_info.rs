@@ -0,0 +1,415 @@
+use crate::{proxy::ProxySettings, tls::TlsConfig};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use color_eyre::{Result, eyre::eyre};
+use openssl::{
+    asn1::{Asn1Time, Asn1TimeRef},
+    x509::{X509, X509NameRef, X509VerifyResult},
+};
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use std::{
+    io::{Read, Write},
+    net::{IpAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// Certificates expiring within this many days are flagged.
+pub const EXPIRY_WARNING_DAYS: i64 = 30;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What an HTTPS server negotiated and presented.
+pub struct TlsDetails {
+    pub version: String,
+    pub cipher: String,
+    pub alpn: Option<String>,
+    /// Why the chain couldn't be verified, `None` when it was.
+    pub verify_error: Option<String>,
+    /// The server's certificate first, then the ones it sent to chain it to
+    /// a root.
+    pub chain: Vec<CertificateDetails>,
+}
+
+pub struct CertificateDetails {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl TlsDetails {
+    /// A warning when the server's certificate has expired or is about to.
+    pub fn expiry_warning(&self) -> Option<String> {
+        let leaf = self.chain.first()?;
+        let days = (leaf.not_after - Utc::now()).num_days();
+        if leaf.not_after <= Utc::now() {
+            Some(format!(
+                "The certificate expired on {}",
+                leaf.not_after.format("%Y-%m-%d")
+            ))
+        } else if days < EXPIRY_WARNING_DAYS {
+            Some(format!("The certificate expires in {} days", days))
+        } else {
+            None
+        }
+    }
+}
+
+fn name(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn time(time: &Asn1TimeRef) -> Result<DateTime<Utc>> {
+    let since_epoch = Asn1Time::from_unix(0)?.diff(time)?;
+    DateTime::from_timestamp(
+        since_epoch.days as i64 * 86_400 + since_epoch.secs as i64,
+        0,
+    )
+    .ok_or_else(|| eyre!("Invalid certificate time {}", time))
+}
+
+fn certificate(certificate: &X509) -> Result<CertificateDetails> {
+    let sans = certificate
+        .subject_alt_names()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| {
+                    if let Some(dns) = name.dnsname() {
+                        return Some(format!("DNS:{}", dns));
+                    }
+                    if let Some(ip) = name.ipaddress() {
+                        let ip = match ip.len() {
+                            4 => IpAddr::from(<[u8; 4]>::try_from(ip).ok()?),
+                            16 => IpAddr::from(<[u8; 16]>::try_from(ip).ok()?),
+                            _ => return None,
+                        };
+                        return Some(format!("IP:{}", ip));
+                    }
+                    name.email()
+                        .map(|email| format!("email:{}", email))
+                        .or_else(|| name.uri().map(|uri| format!("URI:{}", uri)))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(CertificateDetails {
+        subject: name(certificate.subject_name()),
+        issuer: name(certificate.issuer_name()),
+        sans,
+        not_before: time(certificate.not_before())?,
+        not_after: time(certificate.not_after())?,
+    })
+}
+
+/// Connects to the first address of `host` that answers, the way the request
+/// itself would, e.g. falling back to IPv4 when IPv6 isn't reachable.
+fn connect(host: &str, port: u16) -> Result<TcpStream> {
+    let mut last_error = None;
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(match last_error {
+        Some(e) => e.into(),
+        None => eyre!("Could not resolve {}", host),
+    })
+}
+
+/// The proxy's user name and password, decoded.
+fn credentials(proxy: &Url) -> Option<(String, String)> {
+    if proxy.username().is_empty() {
+        return None;
+    }
+    let decode = |text: &str| percent_decode_str(text).decode_utf8_lossy().to_string();
+    Some((
+        decode(proxy.username()),
+        decode(proxy.password().unwrap_or_default()),
+    ))
+}
+
+/// A connection to `host` through an HTTP proxy, opened with `CONNECT`.
+fn http_tunnel(proxy: &Url, host: &str, port: u16) -> Result<TcpStream> {
+    let mut stream = connect(
+        proxy.host_str().unwrap_or_default(),
+        proxy.port_or_known_default().unwrap_or(80),
+    )?;
+    let authority = if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let Some((username, password)) = credentials(proxy) {
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            STANDARD.encode(format!("{}:{}", username, password))
+        ));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    // Read a byte at a time, what follows the headers is the server's.
+    let mut response = Vec::new();
+    let mut byte = [0u8];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > 64 * 1024 {
+            return Err(eyre!("The proxy's CONNECT response is too long"));
+        }
+        stream.read_exact(&mut byte)?;
+        response.push(byte[0]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(eyre!(
+            "The proxy refused to connect to {}: {}",
+            authority,
+            status_line
+        ));
+    }
+
+    Ok(stream)
+}
+
+/// A connection to `host` through a SOCKS5 proxy.
+fn socks_tunnel(proxy: &Url, host: &str, port: u16) -> Result<TcpStream> {
+    let mut stream = connect(
+        proxy.host_str().unwrap_or_default(),
+        proxy.port().unwrap_or(1080),
+    )?;
+    let credentials = credentials(proxy);
+    let methods: &[u8] = if credentials.is_some() {
+        &[0x00, 0x02]
+    } else {
+        &[0x00]
+    };
+    stream.write_all(&[&[0x05, methods.len() as u8], methods].concat())?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply)?;
+    match (reply[1], credentials) {
+        (0x00, _) => {}
+        (0x02, Some((username, password))) => {
+            let mut request = vec![0x01, username.len() as u8];
+            request.extend(username.as_bytes());
+            request.push(password.len() as u8);
+            request.extend(password.as_bytes());
+            stream.write_all(&request)?;
+            stream.read_exact(&mut reply)?;
+            if reply[1] != 0x00 {
+                return Err(eyre!("The SOCKS proxy refused the credentials"));
+            }
+        }
+        _ => {
+            return Err(eyre!(
+                "The SOCKS proxy accepts none of the offered authentication methods"
+            ));
+        }
+    }
+
+    // Only `socks5h` has the proxy resolve the name, like reqwest does.
+    let address = match host.parse::<IpAddr>() {
+        Ok(ip) => Some(ip),
+        Err(_) if proxy.scheme() == "socks5" => Some(
+            (host, port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| eyre!("Could not resolve {}", host))?
+                .ip(),
+        ),
+        Err(_) => None,
+    };
+    let mut request = vec![0x05, 0x01, 0x00];
+    match address {
+        Some(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend(ip.octets());
+        }
+        Some(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend(ip.octets());
+        }
+        None => {
+            request.extend([0x03, host.len() as u8]);
+            request.extend(host.as_bytes());
+        }
+    }
+    request.extend(port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header)?;
+    if header[1] != 0x00 {
+        return Err(eyre!(
+            "The SOCKS proxy could not connect to {}:{} (reply {})",
+            host,
+            port,
+            header[1]
+        ));
+    }
+    // The address the proxy bound, of no use here.
+    let address_length = match header[3] {
+        0x01 => 4,
+        0x04 => 16,
+        _ => {
+            let mut length = [0u8];
+            stream.read_exact(&mut length)?;
+            length[0] as usize
+        }
+    };
+    stream.read_exact(&mut vec![0u8; address_length + 2])?;
+
+    Ok(stream)
+}
+
+fn handshake(host: &str, port: u16, tls: &TlsConfig, proxy: Option<&Url>) -> Result<TlsDetails> {
+    let stream = match proxy {
+        None => connect(host, port)?,
+        Some(proxy) if proxy.scheme() == "http" => http_tunnel(proxy, host, port)?,
+        Some(proxy) if proxy.scheme().starts_with("socks5") => socks_tunnel(proxy, host, port)?,
+        Some(proxy) => {
+            return Err(eyre!(
+                "The TLS details can't be read through a {} proxy",
+                proxy.scheme()
+            ));
+        }
+    };
+    let stream = tls
+        .connector()?
+        .configure()?
+        .connect(host, stream)
+        .map_err(|e| eyre!("TLS handshake failed: {}", e))?;
+    let ssl = stream.ssl();
+
+    let chain = match ssl.peer_cert_chain() {
+        Some(chain) => chain
+            .iter()
+            .map(|cert| certificate(&cert.to_owned()))
+            .collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    let verify_result = ssl.verify_result();
+
+    Ok(TlsDetails {
+        version: ssl.version_str().to_string(),
+        cipher: ssl
+            .current_cipher()
+            .map(|cipher| cipher.name().to_string())
+            .unwrap_or_default(),
+        alpn: ssl
+            .selected_alpn_protocol()
+            .map(|protocol| String::from_utf8_lossy(protocol).to_string()),
+        verify_error: (verify_result != X509VerifyResult::OK)
+            .then(|| verify_result.error_string().to_string()),
+        chain,
+    })
+}
+
+/// Connects to an HTTPS URL again, with the same TLS settings as the request,
+/// to see what was negotiated. reqwest doesn't expose it for the request's
+/// own connection. It goes through the same proxy as the request, if any.
+/// `None` for plain HTTP.
+pub async fn inspect(
+    url: String,
+    tls: TlsConfig,
+    proxy: ProxySettings,
+) -> Option<Result<TlsDetails, String>> {
+    let url = Url::parse(&url).ok()?;
+    if url.scheme() != "https" {
+        return None;
+    }
+    // IPv6 addresses come in brackets.
+    let host = url
+        .host_str()?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = url.port_or_known_default()?;
+    let proxy = proxy.route(&url);
+
+    Some(
+        tokio::task::spawn_blocking(move || handshake(&host, port, &tls, proxy.as_ref()))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|details| details.map_err(|e| e.to_string())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    /// A SOCKS5 proxy that accepts one connection without auth and returns
+    /// the address type and address it was asked to connect to.
+    fn socks_proxy() -> (Url, thread::JoinHandle<(u8, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("socks5://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).unwrap();
+            socket.write_all(&[0x05, 0x00]).unwrap();
+            let mut header = [0u8; 4];
+            socket.read_exact(&mut header).unwrap();
+            let length = match header[3] {
+                0x01 => 4,
+                0x04 => 16,
+                _ => {
+                    let mut length = [0u8];
+                    socket.read_exact(&mut length).unwrap();
+                    length[0] as usize
+                }
+            };
+            let mut address = vec![0u8; length + 2];
+            socket.read_exact(&mut address).unwrap();
+            address.truncate(length);
+            socket
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+            (header[3], address)
+        });
+        (Url::parse(&url).unwrap(), server)
+    }
+
+    #[test]
+    fn socks5_resolves_the_host_locally() {
+        let (proxy, server) = socks_proxy();
+        socks_tunnel(&proxy, "localhost", 443).unwrap();
+        let (address_type, address) = server.join().unwrap();
+        match address_type {
+            0x01 => assert_eq!(address, [127, 0, 0, 1]),
+            0x04 => assert_eq!(address, std::net::Ipv6Addr::LOCALHOST.octets()),
+            other => panic!("sent address type {}", other),
+        }
+    }
+
+    #[test]
+    fn socks5h_has_the_proxy_resolve_the_host() {
+        let (mut proxy, server) = socks_proxy();
+        proxy.set_scheme("socks5h").unwrap();
+        socks_tunnel(&proxy, "localhost", 443).unwrap();
+        assert_eq!(server.join().unwrap(), (0x03, b"localhost".to_vec()));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::DisplayString;
+use ratatui::{prelude::*, widgets::*};
+
+struct SearchMatch {
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Scroll, wrap and search state for the Response panel. The viewer doesn't own
+/// the lines it shows, they're passed in on every call so the pretty/raw body,
+/// the headers and the info view can all share it.
+pub struct ResponseViewer {
+    pub scroll: usize,
+    pub wrap: bool,
+    pub line_numbers: bool,
+    pub search: DisplayString,
+    matches: Vec<SearchMatch>,
+    current_match: Option<usize>,
+    page_height: usize,
+}
+
+impl ResponseViewer {
+    pub fn new() -> Self {
+        Self {
+            scroll: 0,
+            wrap: true,
+            line_numbers: false,
+            search: DisplayString::new(String::new()),
+            matches: Vec::new(),
+            current_match: None,
+            page_height: 1,
+        }
+    }
+
+    pub fn scroll_down(&mut self, amount: usize, total_lines: usize) {
+        self.scroll = (self.scroll + amount).min(total_lines.saturating_sub(1));
+    }
+
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_sub(amount);
+    }
+
+    pub fn page_down(&mut self, total_lines: usize) {
+        self.scroll_down(self.page_height, total_lines);
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.page_height);
+    }
+
+    pub fn scroll_to_bottom(&mut self, total_lines: usize) {
+        self.scroll = total_lines.saturating_sub(self.page_height);
+    }
+
+    pub fn start_search(&mut self) {
+        self.search.update_value(String::new());
+        self.search.edit_mode = true;
+        self.matches.clear();
+        self.current_match = None;
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search.update_value(String::new());
+        self.search.edit_mode = false;
+        self.matches.clear();
+        self.current_match = None;
+    }
+
+    /// Recomputes matches for the current query and jumps to the first one at
+    /// or below the current scroll position.
+    pub fn update_matches(&mut self, lines: &[Line<'static>]) {
+        self.matches.clear();
+        self.current_match = None;
+        let query = self.search.value.to_ascii_lowercase();
+        if query.is_empty() {
+            return;
+        }
+
+        for (index, line) in lines.iter().enumerate() {
+            let content = line_content(line).to_ascii_lowercase();
+            let mut offset = 0;
+            while let Some(found) = content[offset..].find(&query) {
+                let start = offset + found;
+                self.matches.push(SearchMatch {
+                    line: index,
+                    start,
+                    end: start + query.len(),
+                });
+                offset = start + query.len();
+            }
+        }
+
+        let first = self
+            .matches
+            .iter()
+            .position(|m| m.line >= self.scroll)
+            .or((!self.matches.is_empty()).then_some(0));
+        self.select_match(first);
+    }
+
+    pub fn next_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let next = match self.current_match {
+            Some(i) => (i + 1) % self.matches.len(),
+            None => 0,
+        };
+        self.select_match(Some(next));
+    }
+
+    pub fn previous_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let previous = match self.current_match {
+            Some(0) | None => self.matches.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.select_match(Some(previous));
+    }
+
+    fn select_match(&mut self, index: Option<usize>) {
+        self.current_match = index;
+        if let Some(m) = index.map(|i| &self.matches[i])
+            && (m.line < self.scroll || m.line >= self.scroll + self.page_height)
+        {
+            self.scroll = m.line.saturating_sub(self.page_height / 2);
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, lines: &[Line<'static>]) {
+        let show_search_bar = self.search.edit_mode || !self.search.value.is_empty();
+        let [text_area, search_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(if show_search_bar { 1 } else { 0 }),
+        ])
+        .areas(area);
+        self.page_height = (text_area.height as usize).max(1);
+        self.scroll = self.scroll.min(lines.len().saturating_sub(1));
+
+        let gutter_width = if self.line_numbers {
+            lines.len().to_string().len() + 1
+        } else {
+            0
+        };
+        let text_width = (text_area.width as usize)
+            .saturating_sub(gutter_width)
+            .max(1);
+        let gutter_style = Style::default().fg(Color::DarkGray);
+
+        let mut rows: Vec<Line> = Vec::new();
+        for (index, line) in lines.iter().enumerate().skip(self.scroll) {
+            let line = self.highlight_matches(index, line);
+            let pieces = if self.wrap {
+                wrap_line(line, text_width)
+            } else {
+                vec![line]
+            };
+
+            for (piece_index, piece) in pieces.into_iter().enumerate() {
+                let mut spans = Vec::new();
+                if self.line_numbers {
+                    let gutter = if piece_index == 0 {
+                        format!("{:>width$} ", index + 1, width = gutter_width - 1)
+                    } else {
+                        " ".repeat(gutter_width)
+                    };
+                    spans.push(Span::styled(gutter, gutter_style));
+                }
+                let style = piece.style;
+                spans.extend(piece.spans);
+                rows.push(Line::from(spans).style(style));
+                if rows.len() >= self.page_height {
+                    break;
+                }
+            }
+            if rows.len() >= self.page_height {
+                break;
+            }
+        }
+        frame.render_widget(Paragraph::new(rows), text_area);
+
+        if show_search_bar {
+            let cursor = if self.search.edit_mode { "█" } else { "" };
+            let position = match self.current_match {
+                Some(i) => format!("  [{}/{}]", i + 1, self.matches.len()),
+                None => "  [no matches]".to_string(),
+            };
+            let search_bar = Line::from(vec![
+                Span::styled("/", Style::default().fg(Color::LightYellow)),
+                Span::raw(format!("{}{}", self.search.value, cursor)),
+                Span::styled(position, Style::default().fg(Color::DarkGray)),
+            ]);
+            frame.render_widget(Paragraph::new(search_bar), search_area);
+        }
+    }
+
+    fn highlight_matches(&self, index: usize, line: &Line<'static>) -> Line<'static> {
+        let ranges: Vec<(usize, usize, bool)> = self
+            .matches
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.line == index)
+            .map(|(i, m)| (m.start, m.end, Some(i) == self.current_match))
+            .collect();
+        if ranges.is_empty() {
+            return line.clone();
+        }
+
+        let match_style = Style::default().bg(Color::DarkGray);
+        let current_style = Style::default().bg(Color::Yellow).fg(Color::Black);
+        let mut spans = Vec::new();
+        let mut position = 0;
+        for span in &line.spans {
+            let content = span.content.as_ref();
+            let span_end = position + content.len();
+            let mut cursor = position;
+            for &(start, end, current) in &ranges {
+                if end <= cursor || start >= span_end {
+                    continue;
+                }
+                let start = start.max(cursor);
+                let end = end.min(span_end);
+                if start > cursor {
+                    spans.push(Span::styled(
+                        content[cursor - position..start - position].to_string(),
+                        span.style,
+                    ));
+                }
+                spans.push(Span::styled(
+                    content[start - position..end - position].to_string(),
+                    span.style
+                        .patch(if current { current_style } else { match_style }),
+                ));
+                cursor = end;
+            }
+            if cursor < span_end {
+                spans.push(Span::styled(
+                    content[cursor - position..].to_string(),
+                    span.style,
+                ));
+            }
+            position = span_end;
+        }
+
+        Line::from(spans).style(line.style)
+    }
+}
+
+fn line_content(line: &Line) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+fn wrap_line(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    let style = line.style;
+    let mut rows = Vec::new();
+    let mut row: Vec<Span<'static>> = Vec::new();
+    let mut row_width = 0;
+
+    for span in line.spans {
+        let content = span.content.as_ref();
+        let mut start = 0;
+        for (offset, _) in content.char_indices() {
+            if row_width == width {
+                if offset > start {
+                    row.push(Span::styled(content[start..offset].to_string(), span.style));
+                }
+                rows.push(Line::from(std::mem::take(&mut row)).style(style));
+                row_width = 0;
+                start = offset;
+            }
+            row_width += 1;
+        }
+        if start < content.len() {
+            row.push(Span::styled(content[start..].to_string(), span.style));
+        }
+    }
+    rows.push(Line::from(row).style(style));
+
+    rows
+}